@@ -2,15 +2,20 @@ use std::{
     cmp::Ordering,
     error, fmt,
     fs::File,
+    hash::Hash,
     io::{self, BufRead, BufReader},
+    ops::{Add, Sub},
     path::{Path, PathBuf},
 };
 
+use ahash::AHashSet;
+
 #[derive(Debug)]
 enum Day1Error {
     EmptySeq,
     NotFound,
     MultiplyOverflow,
+    InvalidTarget,
 }
 
 impl fmt::Display for Day1Error {
@@ -19,81 +24,132 @@ impl fmt::Display for Day1Error {
             Day1Error::EmptySeq => write!(f, "No values in sequence."),
             Day1Error::NotFound => write!(f, "No answer found"),
             Day1Error::MultiplyOverflow => write!(f, "Multiplication overflow"),
+            Day1Error::InvalidTarget => write!(f, "Invalid target value"),
         }
     }
 }
 
 impl error::Error for Day1Error {}
 
-const TARGET: i32 = 2020;
+const TARGET: i64 = 2020;
 
-fn get_numbers(path: impl AsRef<Path>) -> io::Result<Vec<i32>> {
+fn get_numbers(path: impl AsRef<Path>) -> io::Result<Vec<i64>> {
     let infile = File::open(path)?;
     let mut numbers = BufReader::new(infile)
         .lines()
-        .filter_map(|l| l.map_or(None, |s| s.parse().ok()))
+        .filter_map(|l| l.ok().and_then(|s| s.parse().ok()))
         .collect::<Vec<_>>();
     numbers.sort_unstable();
     Ok(numbers)
 }
 
-fn find_pair(numbers: &[i32], target: i32) -> Result<(i32, i32), Day1Error> {
-    assert!(numbers.len() >= 2);
+fn find_subset<T>(numbers: &[T], target: T, count: usize) -> Option<Vec<T>>
+where
+    T: Add<Output = T> + Sub<Output = T> + Copy + Default + Ord,
+{
     assert!(numbers.windows(2).all(|w| w[0] <= w[1])); // numbers.is_sorted() in unstable
+    find_sorted_subset(numbers, target, count)
+}
 
-    let mut it = numbers.iter();
-
-    let mut low = *it.next().ok_or(Day1Error::EmptySeq)?;
-    let mut high = *it.next_back().ok_or(Day1Error::EmptySeq)?;
+fn find_sorted_subset<T>(numbers: &[T], target: T, count: usize) -> Option<Vec<T>>
+where
+    T: Add<Output = T> + Sub<Output = T> + Copy + Default + Ord,
+{
+    if numbers.len() < count {
+        return None;
+    }
 
-    loop {
-        let total = low + high;
-        match total.cmp(&target) {
-            Ordering::Equal => return Ok((low, high)),
-            Ordering::Less => {
-                low = *it.next().ok_or(Day1Error::NotFound)?;
-            }
-            Ordering::Greater => {
-                high = *it.next_back().ok_or(Day1Error::NotFound)?;
+    match count {
+        0 => Some(Vec::new()).filter(|_| target == T::default()),
+        1 => numbers.binary_search(&target).ok().map(|_| vec![target]),
+        2 => {
+            // two-pointer search from either end of the sorted slice
+            let (mut low, mut high) = (0, numbers.len() - 1);
+            while low < high {
+                match (numbers[low] + numbers[high]).cmp(&target) {
+                    Ordering::Equal => return Some(vec![numbers[low], numbers[high]]),
+                    Ordering::Less => low += 1,
+                    Ordering::Greater => high -= 1,
+                }
             }
+            None
         }
+        _ => numbers[..=numbers.len() - count]
+            .iter()
+            .enumerate()
+            .find_map(|(i, &first)| {
+                let mut subset = find_sorted_subset(&numbers[i + 1..], target - first, count - 1)?;
+                subset.insert(0, first);
+                Some(subset)
+            }),
     }
 }
 
-fn find_triple(numbers: &[i32], target: i32) -> Result<(i32, i32, i32), Day1Error> {
-    assert!(numbers.len() >= 3);
-    assert!(numbers.windows(2).all(|w| w[0] <= w[1])); // numbers.is_sorted() in unstable
+fn find_pair<T>(numbers: &[T], target: T) -> Result<(T, T), Day1Error>
+where
+    T: Add<Output = T> + Sub<Output = T> + Copy + Default + Ord,
+{
+    match find_subset(numbers, target, 2).as_deref() {
+        Some(&[low, high]) => Ok((low, high)),
+        _ => Err(Day1Error::NotFound),
+    }
+}
+
+fn find_triple<T>(numbers: &[T], target: T) -> Result<(T, T, T), Day1Error>
+where
+    T: Add<Output = T> + Sub<Output = T> + Copy + Default + Ord,
+{
+    match find_subset(numbers, target, 3).as_deref() {
+        Some(&[low, middle, high]) => Ok((low, middle, high)),
+        _ => Err(Day1Error::NotFound),
+    }
+}
 
-    numbers[0..numbers.len() - 2]
+fn find_all_pairs<T>(numbers: &[T], target: T) -> Vec<(T, T)>
+where
+    T: Sub<Output = T> + Copy + Hash + Ord,
+{
+    // a value only pairs with an earlier one, so a lone target / 2 cannot pair with itself
+    let mut seen = AHashSet::new();
+    let mut pairs = AHashSet::new();
+    for &number in numbers {
+        let complement = target - number;
+        if seen.contains(&complement) {
+            pairs.insert((number.min(complement), number.max(complement)));
+        }
+        seen.insert(number);
+    }
+
+    let mut pairs = pairs.into_iter().collect::<Vec<_>>();
+    pairs.sort_unstable();
+    pairs
+}
+
+fn product(values: &[i64]) -> Result<i64, Day1Error> {
+    values
         .iter()
-        .enumerate()
-        .filter_map(|(i, &l)| {
-            find_pair(&numbers[i + 1..], target - l)
-                .map(|(m, h)| (l, m, h))
-                .ok()
-        })
-        .next()
-        .ok_or(Day1Error::NotFound)
+        .try_fold(1i64, |acc, &v| acc.checked_mul(v))
+        .ok_or(Day1Error::MultiplyOverflow)
 }
 
-fn part1(numbers: &[i32]) -> Result<(), Day1Error> {
-    let (low, high) = find_pair(numbers, TARGET)?;
-    let product = low.checked_mul(high).ok_or(Day1Error::MultiplyOverflow)?;
+fn part1(numbers: &[i64], target: i64) -> Result<(), Day1Error> {
+    let (low, high) = find_pair(numbers, target)?;
+    let product = product(&[low, high])?;
     println!(
         "Part 1: low = {}, high = {}, product = {}",
         low, high, product
     );
+    println!(
+        "Part 1: {} distinct pairs sum to {}",
+        find_all_pairs(numbers, target).len(),
+        target
+    );
     Ok(())
 }
 
-fn part2(numbers: &[i32]) -> Result<(), Day1Error> {
-    let (low, middle, high) = find_triple(numbers, TARGET)?;
-
-    let product = low
-        .checked_mul(middle)
-        .map(|lm| lm.checked_mul(high))
-        .flatten()
-        .ok_or(Day1Error::MultiplyOverflow)?;
+fn part2(numbers: &[i64], target: i64) -> Result<(), Day1Error> {
+    let (low, middle, high) = find_triple(numbers, target)?;
+    let product = product(&[low, middle, high])?;
 
     println!(
         "Part 2: low = {}, middle = {}, high = {}, product = {}",
@@ -103,12 +159,27 @@ fn part2(numbers: &[i32]) -> Result<(), Day1Error> {
     Ok(())
 }
 
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(i64, PathBuf), Day1Error> {
+    let target = match args.next() {
+        Some(arg) => arg.parse().map_err(|_| Day1Error::InvalidTarget)?,
+        None => TARGET,
+    };
+    let path = args.next().map_or_else(
+        || ["data", "day01", "input.txt"].iter().collect(),
+        PathBuf::from,
+    );
+    Ok((target, path))
+}
+
 fn run() -> Result<(), Box<dyn error::Error>> {
-    let path = ["data", "day01", "input.txt"].iter().collect::<PathBuf>();
+    let (target, path) = parse_args(std::env::args().skip(1))?;
     let numbers = get_numbers(path)?;
+    if numbers.is_empty() {
+        return Err(Day1Error::EmptySeq.into());
+    }
 
-    part1(&numbers)?;
-    part2(&numbers)?;
+    part1(&numbers, target)?;
+    part2(&numbers, target)?;
     Ok(())
 }
 
@@ -124,9 +195,11 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{find_pair, find_triple, TARGET};
+    use std::path::PathBuf;
+
+    use super::{find_all_pairs, find_pair, find_subset, find_triple, parse_args, product, TARGET};
 
-    const NUMBERS: [i32; 6] = [1721, 979, 366, 299, 675, 1456];
+    const NUMBERS: [i64; 6] = [1721, 979, 366, 299, 675, 1456];
 
     #[test]
     fn part1_test() {
@@ -148,4 +221,63 @@ mod test {
         let product = low * middle * high;
         assert_eq!(241861950, product);
     }
+
+    #[test]
+    fn find_subset_test() {
+        let mut numbers = NUMBERS;
+        numbers.sort_unstable();
+        assert_eq!(find_subset(&numbers, TARGET, 2), Some(vec![299, 1721]));
+        assert_eq!(find_subset(&numbers, TARGET, 3), Some(vec![366, 675, 979]));
+        assert_eq!(
+            find_subset(&numbers, 2319, 4),
+            Some(vec![299, 366, 675, 979])
+        );
+        assert_eq!(find_subset(&numbers, TARGET, 4), None);
+        assert_eq!(find_subset(&numbers[..3], 1644, 4), None);
+        assert_eq!(find_subset(&[], TARGET, 2), None);
+    }
+
+    #[test]
+    fn find_all_pairs_test() {
+        assert_eq!(find_all_pairs(&NUMBERS, TARGET), vec![(299, 1721)]);
+        assert_eq!(
+            find_all_pairs(&[1000, 1010, 20, 2000, 1020, 1010, 1000], TARGET),
+            vec![(20, 2000), (1000, 1020), (1010, 1010)]
+        );
+        assert_eq!(find_all_pairs(&[1010, 5, 15], TARGET), vec![]);
+        assert_eq!(find_all_pairs(&[], TARGET), vec![]);
+    }
+
+    #[test]
+    fn parse_args_test() {
+        let default_path = ["data", "day01", "input.txt"].iter().collect::<PathBuf>();
+        let (target, path) = parse_args(std::iter::empty()).unwrap();
+        assert_eq!((target, path), (TARGET, default_path.clone()));
+
+        let (target, path) = parse_args(vec!["1234".to_owned()].into_iter()).unwrap();
+        assert_eq!((target, path), (1234, default_path));
+
+        let args = vec!["42".to_owned(), "other.txt".to_owned()];
+        let (target, path) = parse_args(args.into_iter()).unwrap();
+        assert_eq!((target, path), (42, PathBuf::from("other.txt")));
+
+        let error = parse_args(vec!["abc".to_owned()].into_iter()).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid target value");
+    }
+
+    #[test]
+    fn large_values_test() {
+        let base = i64::from(i32::MAX / 2);
+        let numbers = [base - 20, base - 10, base, base + 10];
+        let target = 3 * base - 10;
+        assert!(target > i64::from(i32::MAX));
+
+        let (low, middle, high) = find_triple(&numbers, target).unwrap();
+        assert_eq!((low, middle, high), (base - 20, base, base + 10));
+        let (low, high) = find_pair(&numbers, 2 * base).unwrap();
+        assert_eq!((low, high), (base - 10, base + 10));
+        assert_eq!(product(&[low, high]).unwrap(), base * base - 100);
+        assert!(base * base - 100 > i64::from(i32::MAX));
+        assert!(product(&[low, middle, high]).is_err());
+    }
 }
@@ -9,11 +9,18 @@ use std::{
 use nom::Finish;
 
 #[derive(Debug)]
-struct ParseError {}
+struct ParseError {
+    line_number: usize,
+    line: String,
+}
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Parse error")
+        write!(
+            f,
+            "Parse error on line {}: {:?}",
+            self.line_number, self.line
+        )
     }
 }
 
@@ -23,7 +30,7 @@ impl Error for ParseError {}
 struct LineInfo {
     pub min: usize,
     pub max: usize,
-    pub character: char,
+    pub character: String,
     pub password: String,
 }
 
@@ -32,7 +39,7 @@ mod line_parsing {
 
     use nom::{
         bytes::complete::tag,
-        character::complete::{alpha1, anychar, char, digit1},
+        character::complete::{alpha1, char, digit1},
         combinator::{map, map_res},
         sequence::separated_pair,
         IResult,
@@ -46,9 +53,9 @@ mod line_parsing {
         )(input)
     }
 
-    fn min_max_char(input: &str) -> IResult<&str, (usize, usize, char)> {
+    fn min_max_char(input: &str) -> IResult<&str, (usize, usize, &str)> {
         map(
-            separated_pair(min_max, char(' '), anychar),
+            separated_pair(min_max, char(' '), alpha1),
             |((min, max), character)| (min, max, character),
         )(input)
     }
@@ -59,63 +66,106 @@ mod line_parsing {
             |((min, max, character), password)| LineInfo {
                 min,
                 max,
-                character,
+                character: character.to_owned(),
                 password: password.to_owned(),
             },
         )(input)
     }
 }
 
-fn split_line(line: impl AsRef<str>) -> Result<LineInfo, ParseError> {
+fn split_line(line: impl AsRef<str>) -> Option<LineInfo> {
     line_parsing::line_parser(line.as_ref())
         .finish()
-        .map_or(Err(ParseError {}), |(_, li)| Ok(li))
+        .ok()
+        .map(|(_, li)| li)
+}
+
+fn parse_lines(reader: impl BufRead) -> Result<Vec<LineInfo>, Box<dyn Error>> {
+    let mut parsed_lines = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_info = split_line(&line).ok_or_else(|| ParseError {
+            line_number: i + 1,
+            line,
+        })?;
+        parsed_lines.push(line_info);
+    }
+
+    Ok(parsed_lines)
+}
+
+fn is_valid(line_info: &LineInfo) -> bool {
+    let occurrence = line_info
+        .password
+        .matches(line_info.character.as_str())
+        .take(line_info.max + 1)
+        .count();
+    (line_info.min..=line_info.max).contains(&occurrence)
+}
+
+fn is_valid2(line_info: &LineInfo) -> bool {
+    let matches_at = |position: usize| {
+        line_info
+            .password
+            .get(position - 1..)
+            .is_some_and(|s| s.starts_with(line_info.character.as_str()))
+    };
+    matches_at(line_info.min) ^ matches_at(line_info.max)
 }
 
+#[cfg(test)]
 fn count_valid<'a>(parsed_lines: impl Iterator<Item = &'a LineInfo>) -> usize {
     parsed_lines
-        .filter(|&line_info| {
-            let occurrence = line_info
-                .password
-                .chars()
-                .filter(|&c| c == line_info.character)
-                .take(line_info.max + 1)
-                .count();
-            (line_info.min..=line_info.max).contains(&occurrence)
-        })
+        .filter(|&line_info| is_valid(line_info))
         .count()
 }
 
+#[cfg(test)]
 fn count_valid2<'a>(parsed_lines: impl Iterator<Item = &'a LineInfo>) -> usize {
     parsed_lines
-        .filter(|&line_info| {
-            let mut password_chars = line_info.password.chars();
-            let first_ok = password_chars
-                .nth(line_info.min - 1)
-                .map_or(false, |c| c == line_info.character);
-            let second_ok = password_chars
-                .nth(line_info.max - line_info.min - 1)
-                .map_or(false, |c| c == line_info.character);
-            first_ok ^ second_ok
-        })
+        .filter(|&line_info| is_valid2(line_info))
         .count()
 }
 
+fn count_both(parsed: &[LineInfo]) -> (usize, usize) {
+    parsed.iter().fold((0, 0), |(count1, count2), line_info| {
+        (
+            count1 + is_valid(line_info) as usize,
+            count2 + is_valid2(line_info) as usize,
+        )
+    })
+}
+
+fn validate(parsed: &[LineInfo]) -> Vec<(usize, bool, bool)> {
+    parsed
+        .iter()
+        .enumerate()
+        .map(|(i, line_info)| (i, is_valid(line_info), is_valid2(line_info)))
+        .collect()
+}
+
 fn run() -> Result<(), Box<dyn Error>> {
     let parsed_lines = {
         let path = ["data", "day02", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
-        let mut parsed_lines = Vec::new();
-        for line in BufReader::new(file).lines() {
-            parsed_lines.push(split_line(line?)?);
-        }
-
-        parsed_lines
+        parse_lines(BufReader::new(file))?
     };
 
-    let part1 = count_valid(parsed_lines.iter());
+    if std::env::args().skip(1).any(|arg| arg == "--report") {
+        for (i, valid1, valid2) in validate(&parsed_lines) {
+            if !(valid1 && valid2) {
+                println!(
+                    "Line {}: policy 1 {}, policy 2 {}",
+                    i + 1,
+                    if valid1 { "passed" } else { "failed" },
+                    if valid2 { "passed" } else { "failed" }
+                );
+            }
+        }
+    }
+
+    let (part1, part2) = count_both(&parsed_lines);
     println!("Part 1: found {} valid passwords", part1);
-    let part2 = count_valid2(parsed_lines.iter());
     println!("Part 2: found {} valid passwords", part2);
     Ok(())
 }
@@ -132,7 +182,11 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{count_valid, count_valid2, split_line, LineInfo};
+    use std::io::Cursor;
+
+    use super::{
+        count_both, count_valid, count_valid2, parse_lines, split_line, validate, LineInfo,
+    };
 
     const TEST_DATA: [&str; 3] = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"];
 
@@ -141,19 +195,19 @@ mod test {
             LineInfo {
                 min: 1,
                 max: 3,
-                character: 'a',
+                character: String::from("a"),
                 password: String::from("abcde"),
             },
             LineInfo {
                 min: 1,
                 max: 3,
-                character: 'b',
+                character: String::from("b"),
                 password: String::from("cdefg"),
             },
             LineInfo {
                 min: 2,
                 max: 9,
-                character: 'c',
+                character: String::from("c"),
                 password: String::from("ccccccccc"),
             },
         ]
@@ -179,4 +233,61 @@ mod test {
         let count = count_valid2(create_test_info().iter());
         assert_eq!(count, 1);
     }
+
+    #[test]
+    fn validate_test() {
+        let info = create_test_info();
+        let result = validate(&info);
+        assert_eq!(
+            result,
+            vec![(0, true, true), (1, false, false), (2, true, false)]
+        );
+        assert_eq!(
+            result.iter().filter(|&&(_, valid1, _)| valid1).count(),
+            count_valid(info.iter())
+        );
+        assert_eq!(
+            result.iter().filter(|&&(_, _, valid2)| valid2).count(),
+            count_valid2(info.iter())
+        );
+    }
+
+    #[test]
+    fn count_both_test() {
+        let info = create_test_info();
+        assert_eq!(
+            count_both(&info),
+            (count_valid(info.iter()), count_valid2(info.iter()))
+        );
+    }
+
+    #[test]
+    fn multi_character_test() {
+        let info = [
+            "1-2 ab: abxaby",
+            "2-3 ab: abababab",
+            "1-3 ab: abzab",
+            "1-4 ab: abxab",
+        ]
+        .iter()
+        .map(|&s| split_line(s).unwrap())
+        .collect::<Vec<_>>();
+        assert_eq!(info[0].character, "ab");
+        assert_eq!(
+            validate(&info),
+            vec![
+                (0, true, true),
+                (1, false, true),
+                (2, true, true),
+                (3, true, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let error = parse_lines(Cursor::new("1-3 a: abcde\n1-3 b cdefg\n")).unwrap_err();
+        assert_eq!(error.to_string(), "Parse error on line 2: \"1-3 b cdefg\"");
+        assert!(split_line("1-3: abc").is_none());
+    }
 }
@@ -1,54 +1,152 @@
 use std::{
+    error::Error,
+    fmt,
     fs::File,
-    io::{self, BufRead, BufReader},
+    io::{BufRead, BufReader},
     path::PathBuf,
 };
 
+#[derive(Debug)]
+struct ParseGridError(String);
+
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Parse error: {}", self.0)
+    }
+}
+
+impl Error for ParseGridError {}
+
+#[derive(Debug)]
+struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid arguments: {}", self.0)
+    }
+}
+
+impl Error for ArgsError {}
+
 const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
 
-fn count_trees(
-    lines: impl Iterator<Item = impl AsRef<str>>,
-    right_step: usize,
-    down_step: usize,
-) -> u32 {
-    let mut pos = 0;
-    let mut trees = 0;
-    for line_ref in lines.step_by(down_step) {
-        let line = line_ref.as_ref();
-        if line.as_bytes()[pos] == b'#' {
-            trees += 1;
+struct Grid {
+    width: usize,
+    height: usize,
+    trees: Vec<bool>,
+}
+
+impl Grid {
+    fn parse(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<Self, ParseGridError> {
+        let mut width = 0;
+        let mut height = 0;
+        let mut trees = Vec::new();
+        for line_ref in lines {
+            let line = line_ref.as_ref();
+            if height == 0 {
+                width = line.len();
+            } else if line.len() != width {
+                return Err(ParseGridError(format!(
+                    "row {} has width {}, expected {}",
+                    height + 1,
+                    line.len(),
+                    width
+                )));
+            }
+
+            for b in line.bytes() {
+                trees.push(match b {
+                    b'#' => true,
+                    b'.' => false,
+                    _ => {
+                        return Err(ParseGridError(format!(
+                            "unexpected character {:?} in row {}",
+                            b as char,
+                            height + 1
+                        )))
+                    }
+                });
+            }
+
+            height += 1;
+        }
+
+        if width == 0 {
+            return Err(ParseGridError("grid is empty".to_owned()));
         }
 
-        pos = (pos + right_step) % line.len();
+        Ok(Self {
+            width,
+            height,
+            trees,
+        })
+    }
+
+    fn tree_positions(&self, right: usize, down: usize) -> Vec<(usize, usize)> {
+        // the map repeats horizontally, so wrap the column around the row width
+        (0..self.height)
+            .step_by(down)
+            .enumerate()
+            .map(|(step, row)| (row, (step * right) % self.width))
+            .filter(|&(row, col)| self.trees[row * self.width + col])
+            .collect()
     }
 
-    trees
+    fn count_slope(&self, right: usize, down: usize) -> u64 {
+        self.tree_positions(right, down).len() as u64
+    }
 }
 
-fn part1(lines: impl Iterator<Item = impl AsRef<str>>) {
-    let trees = count_trees(lines, 3, 1);
+fn part1(grid: &Grid) {
+    let trees = grid.count_slope(3, 1);
     println!("Part 1: encountered {} trees", trees);
 }
 
-fn part2(lines: impl Iterator<Item = impl AsRef<str>> + Clone) {
-    let result = SLOPES
+fn slope_product(grid: &Grid, slopes: &[(usize, usize)]) -> u64 {
+    slopes
         .iter()
-        .map(|&(right_step, down_step)| count_trees(lines.clone(), right_step, down_step))
-        .product::<u32>();
+        .map(|&(right, down)| grid.count_slope(right, down))
+        .product()
+}
+
+fn part2(grid: &Grid, slopes: &[(usize, usize)]) {
+    let result = slope_product(grid, slopes);
     println!("Part 2: product is {}", result);
 }
 
-fn run() -> Result<(), io::Error> {
-    let lines = {
+fn parse_slopes(args: impl Iterator<Item = String>) -> Result<Vec<(usize, usize)>, ArgsError> {
+    let mut slopes = Vec::new();
+    for arg in args {
+        for slope in arg.split_whitespace() {
+            let (right, down) = slope
+                .split_once(',')
+                .and_then(|(r, d)| Some((r.parse().ok()?, d.parse().ok()?)))
+                .filter(|&(_, down)| down > 0)
+                .ok_or_else(|| ArgsError(format!("invalid slope {:?}", slope)))?;
+            slopes.push((right, down));
+        }
+    }
+
+    if slopes.is_empty() {
+        slopes.extend_from_slice(&SLOPES);
+    }
+
+    Ok(slopes)
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let slopes = parse_slopes(std::env::args().skip(1))?;
+    let grid = {
         let path = ["data", "day03", "input.txt"].iter().collect::<PathBuf>();
         let input_file = File::open(path)?;
-        BufReader::new(input_file)
+        let lines = BufReader::new(input_file)
             .lines()
-            .collect::<Result<Vec<_>, _>>()?
+            .collect::<Result<Vec<_>, _>>()?;
+        Grid::parse(lines.iter())?
     };
 
-    part1(lines.iter());
-    part2(lines.iter());
+    part1(&grid);
+    part2(&grid, &slopes);
     Ok(())
 }
 
@@ -64,7 +162,7 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{count_trees, SLOPES};
+    use super::{parse_slopes, slope_product, Grid, SLOPES};
 
     const EXAMPLE_LAYOUT: [&str; 11] = [
         "..##.......",
@@ -82,22 +180,76 @@ mod test {
 
     #[test]
     fn part1() {
-        let trees = count_trees(EXAMPLE_LAYOUT.iter(), 3, 1);
+        let grid = Grid::parse(EXAMPLE_LAYOUT.iter()).unwrap();
+        let trees = grid.count_slope(3, 1);
         assert_eq!(trees, 7);
     }
 
     #[test]
     fn part2() {
-        let iter = EXAMPLE_LAYOUT.iter();
+        let grid = Grid::parse(EXAMPLE_LAYOUT.iter()).unwrap();
         let trees = SLOPES
             .iter()
-            .map(|&(right_step, down_step)| count_trees(iter.clone(), right_step, down_step))
+            .map(|&(right, down)| grid.count_slope(right, down))
             .collect::<Vec<_>>();
 
         let expected = [2, 7, 3, 4, 2];
 
         assert_eq!(trees, expected);
 
-        assert_eq!(trees.iter().product::<u32>(), 336);
+        assert_eq!(trees.iter().product::<u64>(), 336);
+    }
+
+    #[test]
+    fn parse_error() {
+        let error = Grid::parse(["..#", "#.", "..."].iter()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Parse error: row 2 has width 2, expected 3"
+        );
+        let error = Grid::parse(["..#", "#.x"].iter()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Parse error: unexpected character 'x' in row 2"
+        );
+        assert!(Grid::parse(std::iter::empty::<&str>()).is_err());
+    }
+
+    #[test]
+    fn custom_slopes() {
+        let grid = Grid::parse(EXAMPLE_LAYOUT.iter()).unwrap();
+        assert_eq!(slope_product(&grid, &[(3, 1), (1, 2)]), 14);
+        assert_eq!(slope_product(&grid, &SLOPES), 336);
+    }
+
+    #[test]
+    fn parse_slopes_test() {
+        let slopes = parse_slopes(vec!["3,1 1,2".to_owned(), "5,1".to_owned()].into_iter());
+        assert_eq!(slopes.unwrap(), vec![(3, 1), (1, 2), (5, 1)]);
+        assert_eq!(parse_slopes(std::iter::empty()).unwrap(), SLOPES.to_vec());
+        let error = parse_slopes(vec!["3,0".to_owned()].into_iter()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid arguments: invalid slope \"3,0\""
+        );
+        assert!(parse_slopes(vec!["3".to_owned()].into_iter()).is_err());
+    }
+
+    #[test]
+    fn large_product() {
+        let grid = Grid::parse(vec!["#"; 70_000].iter()).unwrap();
+        assert_eq!(grid.count_slope(1, 1), 70_000);
+        let product = slope_product(&grid, &[(1, 1), (3, 1)]);
+        assert!(product > u64::from(u32::MAX));
+        assert_eq!(product, 4_900_000_000);
+    }
+
+    #[test]
+    fn tree_positions() {
+        let grid = Grid::parse(EXAMPLE_LAYOUT.iter()).unwrap();
+        assert_eq!(
+            grid.tree_positions(3, 1),
+            vec![(2, 6), (4, 1), (5, 4), (7, 10), (8, 2), (9, 5), (10, 8)]
+        );
     }
 }
@@ -1,95 +1,279 @@
 use std::{
     error::Error,
+    fmt,
     fs::File,
     io::{BufRead, BufReader},
+    ops::RangeInclusive,
     path::PathBuf,
+    str::FromStr,
 };
 
 use ahash::AHashSet;
 
 const REQUIRED_FIELDS: [&str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
-const EYE_COLORS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
 
-fn check_valid(prefixes: &AHashSet<String>) -> bool {
-    REQUIRED_FIELDS
-        .iter()
-        .all(|f| prefixes.contains(f.to_owned()))
+#[derive(Debug, PartialEq)]
+enum FieldError {
+    Missing(&'static str),
+    OutOfRange(String),
+    WrongLength(String),
+    BadFormat(String),
+    UnknownEyeColor(String),
+    UnknownField(String),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldError::Missing(prefix) => write!(f, "{}: field is missing", prefix),
+            FieldError::OutOfRange(prefix) => write!(f, "{}: value out of range", prefix),
+            FieldError::WrongLength(prefix) => write!(f, "{}: value has wrong length", prefix),
+            FieldError::BadFormat(prefix) => write!(f, "{}: value has bad format", prefix),
+            FieldError::UnknownEyeColor(prefix) => write!(f, "{}: unknown eye color", prefix),
+            FieldError::UnknownField(prefix) => write!(f, "{}: unknown field", prefix),
+        }
+    }
+}
+
+impl Error for FieldError {}
+
+const BIRTH_YEARS: RangeInclusive<u16> = 1920..=2002;
+const ISSUE_YEARS: RangeInclusive<u16> = 2010..=2020;
+const EXPIRATION_YEARS: RangeInclusive<u16> = 2020..=2030;
+
+fn parse_year(prefix: &str, value: &str) -> Result<u16, FieldError> {
+    if value.len() != 4 {
+        return Err(FieldError::WrongLength(prefix.to_owned()));
+    }
+
+    value
+        .parse()
+        .map_err(|_| FieldError::BadFormat(prefix.to_owned()))
+}
+
+fn validate_year(prefix: &str, value: &str, range: RangeInclusive<u16>) -> Result<u16, FieldError> {
+    let year = parse_year(prefix, value)?;
+    if range.contains(&year) {
+        Ok(year)
+    } else {
+        Err(FieldError::OutOfRange(prefix.to_owned()))
+    }
 }
 
-fn value_ok(prefix: &str, value: &str) -> bool {
+fn validate_hair_color(value: &str) -> Result<(), FieldError> {
+    if value.len() != 7 {
+        Err(FieldError::WrongLength("hcl".to_owned()))
+    } else if value.starts_with('#') && value[1..].chars().all(|c| "0123456789abcdef".contains(c)) {
+        Ok(())
+    } else {
+        Err(FieldError::BadFormat("hcl".to_owned()))
+    }
+}
+
+fn validate_passport_id(value: &str) -> Result<(), FieldError> {
+    if value.len() != 9 {
+        Err(FieldError::WrongLength("pid".to_owned()))
+    } else if value.chars().all(|c| "0123456789".contains(c)) {
+        Ok(())
+    } else {
+        Err(FieldError::BadFormat("pid".to_owned()))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum FieldValue {
+    BirthYear(u16),
+    IssueYear(u16),
+    ExpirationYear(u16),
+    Height(Height),
+    HairColor(String),
+    EyeColor(EyeColor),
+    PassportId(String),
+    CountryId,
+}
+
+fn validate_field(prefix: &str, value: &str) -> Result<FieldValue, FieldError> {
     match prefix {
-        "byr" => value.len() == 4 && (1920..=2002).contains(&value.parse::<i32>().unwrap_or(0)),
-        "iyr" => value.len() == 4 && (2010..=2020).contains(&value.parse::<i32>().unwrap_or(0)),
-        "eyr" => value.len() == 4 && (2020..=2030).contains(&value.parse::<i32>().unwrap_or(0)),
-        "hgt" => {
-            if let Some(height) = value.strip_suffix("cm") {
-                (150..=193).contains(&height.parse::<i32>().unwrap_or(0))
-            } else if let Some(height) = value.strip_suffix("in") {
-                (59..=76).contains(&height.parse::<i32>().unwrap_or(0))
+        "byr" => validate_year(prefix, value, BIRTH_YEARS).map(FieldValue::BirthYear),
+        "iyr" => validate_year(prefix, value, ISSUE_YEARS).map(FieldValue::IssueYear),
+        "eyr" => validate_year(prefix, value, EXPIRATION_YEARS).map(FieldValue::ExpirationYear),
+        "hgt" => value.parse().map(FieldValue::Height),
+        "hcl" => validate_hair_color(value).map(|_| FieldValue::HairColor(value.to_owned())),
+        "ecl" => value.parse().map(FieldValue::EyeColor),
+        "pid" => validate_passport_id(value).map(|_| FieldValue::PassportId(value.to_owned())),
+        "cid" => Ok(FieldValue::CountryId),
+        _ => Err(FieldError::UnknownField(prefix.to_owned())),
+    }
+}
+
+fn fields<'a>(lines: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    lines
+        .iter()
+        .flat_map(|line| line.split(' '))
+        .filter_map(|field| {
+            let pos = field.find(':')?;
+            Some((&field[..pos], &field[pos + 1..]))
+        })
+        .collect()
+}
+
+fn validate_passport(fields: &[(&str, &str)]) -> Vec<FieldError> {
+    let mut errors = fields
+        .iter()
+        .filter_map(|&(prefix, value)| validate_field(prefix, value).err())
+        .collect::<Vec<_>>();
+    errors.extend(
+        REQUIRED_FIELDS
+            .iter()
+            .filter(|&&required| fields.iter().all(|&(prefix, _)| prefix != required))
+            .map(|&required| FieldError::Missing(required)),
+    );
+    errors
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Height {
+    Cm(u16),
+    In(u16),
+}
+
+impl FromStr for Height {
+    type Err = FieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (height, range, constructor): (_, _, fn(u16) -> Self) =
+            if let Some(height) = s.strip_suffix("cm") {
+                (height, 150..=193, Height::Cm)
+            } else if let Some(height) = s.strip_suffix("in") {
+                (height, 59..=76, Height::In)
             } else {
-                false
-            }
+                return Err(FieldError::BadFormat("hgt".to_owned()));
+            };
+
+        let height = height
+            .parse()
+            .map_err(|_| FieldError::BadFormat("hgt".to_owned()))?;
+        if range.contains(&height) {
+            Ok(constructor(height))
+        } else {
+            Err(FieldError::OutOfRange("hgt".to_owned()))
         }
-        "hcl" => {
-            if value.len() == 7 {
-                let mut chars = value.chars();
-                chars.next().unwrap() == '#' && chars.all(|c| "0123456789abcdef".contains(c))
-            } else {
-                false
-            }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EyeColor {
+    Amb,
+    Blu,
+    Brn,
+    Gry,
+    Grn,
+    Hzl,
+    Oth,
+}
+
+impl FromStr for EyeColor {
+    type Err = FieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "amb" => Ok(EyeColor::Amb),
+            "blu" => Ok(EyeColor::Blu),
+            "brn" => Ok(EyeColor::Brn),
+            "gry" => Ok(EyeColor::Gry),
+            "grn" => Ok(EyeColor::Grn),
+            "hzl" => Ok(EyeColor::Hzl),
+            "oth" => Ok(EyeColor::Oth),
+            _ => Err(FieldError::UnknownEyeColor("ecl".to_owned())),
         }
-        "ecl" => EYE_COLORS.contains(&value),
-        "pid" => value.len() == 9 && value.chars().all(|c| "0123456789".contains(c)),
-        _ => false,
-    }
-}
-
-fn count_valid<S, L, F>(lines: L, check: F) -> usize
-where
-    S: AsRef<str>,
-    L: Iterator<Item = S>,
-    F: Fn(&str, &str) -> bool,
-{
-    let mut valid = 0;
-    let mut prefixes = AHashSet::new();
-    for line_ref in lines {
-        let line = line_ref.as_ref();
-        if line.is_empty() {
-            if check_valid(&prefixes) {
-                valid += 1;
-            }
+    }
+}
 
-            prefixes.clear();
-        } else {
-            for field in line.split(' ') {
-                if let Some(pos) = field.find(':') {
-                    let (prefix, value) = field.split_at(pos);
-                    if check(prefix, &value[1..]) {
-                        prefixes.insert(prefix.to_owned());
-                    }
-                }
+// Typed fields are only populated when validate_field accepts the value, while present records
+// every prefix seen so that the part 1 check can ignore the values.
+#[derive(Debug, Default)]
+struct Passport {
+    present: AHashSet<String>,
+    byr: Option<u16>,
+    iyr: Option<u16>,
+    eyr: Option<u16>,
+    hgt: Option<Height>,
+    hcl: Option<String>,
+    ecl: Option<EyeColor>,
+    pid: Option<String>,
+}
+
+impl Passport {
+    fn from_block(lines: &[&str]) -> Passport {
+        let mut passport = Passport::default();
+        for (prefix, value) in fields(lines) {
+            passport.present.insert(prefix.to_owned());
+            // invalid values are reported by validate_passport, so they are simply left unset here
+            match validate_field(prefix, value) {
+                Ok(FieldValue::BirthYear(year)) => passport.byr = Some(year),
+                Ok(FieldValue::IssueYear(year)) => passport.iyr = Some(year),
+                Ok(FieldValue::ExpirationYear(year)) => passport.eyr = Some(year),
+                Ok(FieldValue::Height(height)) => passport.hgt = Some(height),
+                Ok(FieldValue::HairColor(color)) => passport.hcl = Some(color),
+                Ok(FieldValue::EyeColor(color)) => passport.ecl = Some(color),
+                Ok(FieldValue::PassportId(id)) => passport.pid = Some(id),
+                Ok(FieldValue::CountryId) | Err(_) => (),
             }
         }
+
+        passport
     }
 
-    if check_valid(&prefixes) {
-        valid + 1
-    } else {
-        valid
+    fn has_required_fields(&self) -> bool {
+        REQUIRED_FIELDS.iter().all(|&f| self.present.contains(f))
+    }
+
+    fn is_valid(&self) -> bool {
+        self.byr.is_some()
+            && self.iyr.is_some()
+            && self.eyr.is_some()
+            && self.hgt.is_some()
+            && self.hcl.is_some()
+            && self.ecl.is_some()
+            && self.pid.is_some()
+    }
+}
+
+fn split_blocks<'a>(lines: &'a [&'a str]) -> impl Iterator<Item = &'a [&'a str]> {
+    lines
+        .split(|line| line.is_empty())
+        .filter(|block| !block.is_empty())
+}
+
+fn parse_passports(lines: impl Iterator<Item = impl AsRef<str>>) -> Vec<Passport> {
+    let lines = lines.collect::<Vec<_>>();
+    let lines = lines.iter().map(|l| l.as_ref()).collect::<Vec<_>>();
+    split_blocks(&lines).map(Passport::from_block).collect()
+}
+
+fn report(lines: impl Iterator<Item = impl AsRef<str>>) {
+    let lines = lines.collect::<Vec<_>>();
+    let lines = lines.iter().map(|l| l.as_ref()).collect::<Vec<_>>();
+    for (i, block) in split_blocks(&lines).enumerate() {
+        let errors = validate_passport(&fields(block));
+        if !errors.is_empty() {
+            let reasons = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+            println!("Passport {}: {}", i + 1, reasons.join(", "));
+        }
     }
 }
 
-fn part1(lines: impl Iterator<Item = impl AsRef<str>>) {
+fn part1(passports: &[Passport]) {
     println!(
         "Part 1: found {} valid passports",
-        count_valid(lines, |_, _| true)
+        passports.iter().filter(|p| p.has_required_fields()).count()
     );
 }
 
-pub fn part2(lines: impl Iterator<Item = impl AsRef<str>>) {
+fn part2(passports: &[Passport]) {
     println!(
         "Part 2: found {} valid passports",
-        count_valid(lines, value_ok)
+        passports.iter().filter(|p| p.is_valid()).count()
     );
 }
 
@@ -102,8 +286,13 @@ fn run() -> Result<(), Box<dyn Error>> {
             .collect::<Result<Vec<_>, _>>()?
     };
 
-    part1(lines.iter());
-    part2(lines.iter());
+    if std::env::args().skip(1).any(|arg| arg == "--report") {
+        report(lines.iter());
+    }
+
+    let passports = parse_passports(lines.iter());
+    part1(&passports);
+    part2(&passports);
 
     Ok(())
 }
@@ -120,7 +309,10 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{count_valid, value_ok};
+    use super::{
+        fields, parse_passports, validate_field, validate_passport, EyeColor, FieldError,
+        FieldValue, Height,
+    };
 
     #[test]
     fn part1() {
@@ -139,7 +331,9 @@ mod test {
             "hcl:#cfa07d eyr:2025 pid:166559648",
             "iyr:2011 ecl:brn hgt:59in",
         ];
-        let valid = count_valid(EXAMPLE.iter(), |_, _| true);
+        let passports = parse_passports(EXAMPLE.iter());
+        assert_eq!(passports.len(), 4);
+        let valid = passports.iter().filter(|p| p.has_required_fields()).count();
         assert_eq!(valid, 2);
     }
 
@@ -160,7 +354,8 @@ mod test {
             "eyr:2038 hcl:74454a iyr:2023",
             "pid:3556412378 byr:2007",
         ];
-        let valid = count_valid(EXAMPLES_INVALID.iter(), value_ok);
+        let passports = parse_passports(EXAMPLES_INVALID.iter());
+        let valid = passports.iter().filter(|p| p.is_valid()).count();
         assert_eq!(valid, 0);
     }
 
@@ -180,7 +375,165 @@ mod test {
             "",
             "iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719",
         ];
-        let valid = count_valid(EXAMPLES_VALID.iter(), value_ok);
+        let passports = parse_passports(EXAMPLES_VALID.iter());
+        let valid = passports.iter().filter(|p| p.is_valid()).count();
         assert_eq!(valid, 4);
     }
+
+    #[test]
+    fn typed_fields() {
+        let passports = parse_passports(
+            [
+                "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980",
+                "hcl:#623a2f",
+                "",
+                "hgt:59cm ecl:zzz eyr:2038 hcl:74454a",
+            ]
+            .iter(),
+        );
+        assert_eq!(passports[0].byr, Some(1980));
+        assert_eq!(passports[0].hgt, Some(Height::In(74)));
+        assert_eq!(passports[0].ecl, Some(EyeColor::Grn));
+        assert_eq!(passports[0].pid.as_deref(), Some("087499704"));
+        assert_eq!(passports[0].hcl.as_deref(), Some("#623a2f"));
+        assert!(passports[0].is_valid());
+
+        assert!(passports[1].present.contains("hgt"));
+        assert_eq!(passports[1].hgt, None);
+        assert_eq!(passports[1].ecl, None);
+        assert_eq!(passports[1].eyr, None);
+        assert_eq!(passports[1].hcl, None);
+        assert!(!passports[1].is_valid());
+    }
+
+    #[test]
+    fn field_errors() {
+        assert_eq!(
+            validate_field("byr", "2002"),
+            Ok(FieldValue::BirthYear(2002))
+        );
+        assert_eq!(
+            validate_field("hgt", "190cm"),
+            Ok(FieldValue::Height(Height::Cm(190)))
+        );
+        assert_eq!(validate_field("cid", "x"), Ok(FieldValue::CountryId));
+        assert_eq!(
+            validate_field("byr", "2003"),
+            Err(FieldError::OutOfRange("byr".to_owned()))
+        );
+        assert_eq!(
+            validate_field("hgt", "190"),
+            Err(FieldError::BadFormat("hgt".to_owned()))
+        );
+        assert_eq!(
+            validate_field("hcl", "#123abz"),
+            Err(FieldError::BadFormat("hcl".to_owned()))
+        );
+        assert_eq!(
+            validate_field("pid", "0123456789"),
+            Err(FieldError::WrongLength("pid".to_owned()))
+        );
+        assert_eq!(
+            validate_field("ecl", "wat"),
+            Err(FieldError::UnknownEyeColor("ecl".to_owned()))
+        );
+    }
+
+    #[test]
+    fn passport_errors() {
+        let errors = validate_passport(&fields(&[
+            "eyr:1972 cid:100",
+            "hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926",
+        ]));
+        assert_eq!(
+            errors,
+            vec![
+                FieldError::OutOfRange("eyr".to_owned()),
+                FieldError::BadFormat("hgt".to_owned()),
+                FieldError::WrongLength("pid".to_owned()),
+            ]
+        );
+
+        let errors = validate_passport(&fields(&[
+            "iyr:2019",
+            "hcl:#602927 eyr:1967 hgt:170cm",
+            "ecl:grn pid:012533040 byr:1946",
+        ]));
+        assert_eq!(errors, vec![FieldError::OutOfRange("eyr".to_owned())]);
+
+        let errors = validate_passport(&fields(&[
+            "hcl:dab227 iyr:2012",
+            "ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277",
+        ]));
+        assert_eq!(errors, vec![FieldError::WrongLength("hcl".to_owned())]);
+
+        let errors = validate_passport(&fields(&[
+            "hgt:59cm ecl:zzz",
+            "eyr:2038 hcl:74454a iyr:2023",
+            "pid:3556412378 byr:2007",
+        ]));
+        assert_eq!(
+            errors,
+            vec![
+                FieldError::OutOfRange("hgt".to_owned()),
+                FieldError::UnknownEyeColor("ecl".to_owned()),
+                FieldError::OutOfRange("eyr".to_owned()),
+                FieldError::WrongLength("hcl".to_owned()),
+                FieldError::OutOfRange("iyr".to_owned()),
+                FieldError::WrongLength("pid".to_owned()),
+                FieldError::OutOfRange("byr".to_owned()),
+            ]
+        );
+
+        let errors = validate_passport(&fields(&["byr:1980 iyr:2012 eyr:2030 cid:1"]));
+        assert_eq!(
+            errors,
+            vec![
+                FieldError::Missing("hgt"),
+                FieldError::Missing("hcl"),
+                FieldError::Missing("ecl"),
+                FieldError::Missing("pid"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_height() {
+        assert_eq!("150cm".parse(), Ok(Height::Cm(150)));
+        assert_eq!("193cm".parse(), Ok(Height::Cm(193)));
+        assert_eq!("59in".parse(), Ok(Height::In(59)));
+        assert_eq!("76in".parse(), Ok(Height::In(76)));
+        for &value in &["149cm", "194cm", "58in", "77in"] {
+            assert_eq!(
+                value.parse::<Height>(),
+                Err(FieldError::OutOfRange("hgt".to_owned()))
+            );
+        }
+        for &value in &["170", "cm", "-5cm", "70ft", "99999cm"] {
+            assert_eq!(
+                value.parse::<Height>(),
+                Err(FieldError::BadFormat("hgt".to_owned()))
+            );
+        }
+    }
+
+    #[test]
+    fn parse_eye_color() {
+        let expected = [
+            ("amb", EyeColor::Amb),
+            ("blu", EyeColor::Blu),
+            ("brn", EyeColor::Brn),
+            ("gry", EyeColor::Gry),
+            ("grn", EyeColor::Grn),
+            ("hzl", EyeColor::Hzl),
+            ("oth", EyeColor::Oth),
+        ];
+        for &(value, color) in &expected {
+            assert_eq!(value.parse(), Ok(color));
+        }
+        assert_eq!(
+            "AMB".parse::<EyeColor>(),
+            Err(FieldError::UnknownEyeColor("ecl".to_owned()))
+        );
+    }
 }
@@ -10,6 +10,8 @@ use std::{
 enum Day5Error {
     NoData,
     NotFound,
+    InvalidLength(usize),
+    InvalidCharacter(char),
 }
 
 impl fmt::Display for Day5Error {
@@ -17,25 +19,57 @@ impl fmt::Display for Day5Error {
         match self {
             Day5Error::NoData => write!(f, "No data"),
             Day5Error::NotFound => write!(f, "Result not found"),
+            Day5Error::InvalidLength(length) => write!(f, "Invalid pass length {}", length),
+            Day5Error::InvalidCharacter(c) => write!(f, "Invalid character {:?} in pass", c),
         }
     }
 }
 
 impl Error for Day5Error {}
 
-fn calculate_id(pass: &str) -> i32 {
-    pass.chars().fold(0, |acc, c| {
-        (acc << 1)
-            + match c {
-                'B' | 'R' => 1,
-                _ => 0,
-            }
+fn decode_bits(part: &str, zero: char, one: char) -> Result<u8, Day5Error> {
+    part.chars().try_fold(0, |acc, c| match c {
+        c if c == zero => Ok(acc << 1),
+        c if c == one => Ok((acc << 1) + 1),
+        _ => Err(Day5Error::InvalidCharacter(c)),
     })
 }
 
+fn decode(pass: &str) -> Result<(u8, u8), Day5Error> {
+    let length = pass.chars().count();
+    if length != 10 {
+        return Err(Day5Error::InvalidLength(length));
+    }
+
+    // only ASCII characters are valid, so anything else is reported before splitting
+    if let Some(c) = pass.chars().find(|c| !c.is_ascii()) {
+        return Err(Day5Error::InvalidCharacter(c));
+    }
+
+    let (row_part, column_part) = pass.split_at(7);
+    let row = decode_bits(row_part, 'F', 'B')?;
+    let column = decode_bits(column_part, 'L', 'R')?;
+    Ok((row, column))
+}
+
+fn calculate_id(pass: &str) -> Result<i32, Day5Error> {
+    let (row, column) = decode(pass)?;
+    Ok(i32::from(row) * 8 + i32::from(column))
+}
+
+fn find_missing_seat(ids: &[i32]) -> Option<i32> {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.windows(2)
+        .find(|&pair| pair[1] - pair[0] == 2)
+        .map(|pair| pair[0] + 1)
+}
+
 fn part1(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<(), Day5Error> {
     let max_value = lines
         .map(|l| calculate_id(l.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
         .max()
         .ok_or(Day5Error::NoData)?;
     println!("Part 1: maximum ID = {}", max_value);
@@ -43,13 +77,11 @@ fn part1(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<(), Day5Error>
 }
 
 fn part2(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<(), Day5Error> {
-    let mut ids = lines.map(|l| calculate_id(l.as_ref())).collect::<Vec<_>>();
-    ids.sort_unstable();
-    let pair = ids
-        .windows(2)
-        .find(|&pair| pair[1] - pair[0] == 2)
-        .ok_or(Day5Error::NotFound)?;
-    println!("Part 2, found empty seat at {}", pair[0] + 1);
+    let ids = lines
+        .map(|l| calculate_id(l.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let seat = find_missing_seat(&ids).ok_or(Day5Error::NotFound)?;
+    println!("Part 2, found empty seat at {}", seat);
     Ok(())
 }
 
@@ -78,7 +110,7 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::calculate_id;
+    use super::{calculate_id, decode, find_missing_seat, Day5Error};
 
     const EXAMPLE_IDS: [(&str, i32); 4] = [
         ("FBFBBFFRLR", 357),
@@ -90,8 +122,48 @@ mod test {
     #[test]
     fn parse_test() {
         for &(pass, expected_id) in &EXAMPLE_IDS {
-            let actual_id = calculate_id(pass);
+            let actual_id = calculate_id(pass).unwrap();
             assert_eq!(actual_id, expected_id);
         }
     }
+
+    #[test]
+    fn decode_test() {
+        assert_eq!(decode("FBFBBFFRLR").unwrap(), (44, 5));
+        assert_eq!(decode("BBBBBBBRRR").unwrap(), (127, 7));
+        assert!(matches!(
+            decode("FBFBBFFRL"),
+            Err(Day5Error::InvalidLength(9))
+        ));
+        assert!(matches!(
+            decode("FBFBBFFRLRR"),
+            Err(Day5Error::InvalidLength(11))
+        ));
+        assert!(matches!(
+            decode("FBFBBFRRLR"),
+            Err(Day5Error::InvalidCharacter('R'))
+        ));
+        assert!(matches!(
+            decode("FBFBBFFBLR"),
+            Err(Day5Error::InvalidCharacter('B'))
+        ));
+        assert!(matches!(
+            decode("FBFBBFFRLé"),
+            Err(Day5Error::InvalidCharacter('é'))
+        ));
+    }
+
+    #[test]
+    fn missing_seat_test() {
+        assert_eq!(find_missing_seat(&[12, 9, 8, 11, 13]), Some(10));
+        assert_eq!(find_missing_seat(&[8, 9, 10]), None);
+        assert_eq!(find_missing_seat(&[]), None);
+    }
+
+    #[test]
+    fn invalid_character_test() {
+        let error = calculate_id("FBFBBFFRLX").unwrap_err();
+        assert!(matches!(error, Day5Error::InvalidCharacter('X')));
+        assert_eq!(error.to_string(), "Invalid character 'X' in pass");
+    }
 }
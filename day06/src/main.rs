@@ -5,48 +5,39 @@ use std::{
     path::PathBuf,
 };
 
-use ahash::AHashSet;
-
-fn part1(lines: impl Iterator<Item = impl AsRef<str>>) -> usize {
-    let mut current = AHashSet::new();
-    let mut question_sum = 0;
-    for line_ref in lines {
-        let line = line_ref.as_ref();
-        if line.is_empty() {
-            question_sum += current.len();
-            current.clear();
-        } else {
-            for c in line.chars() {
-                current.insert(c);
-            }
-        }
-    }
-
-    question_sum + current.len()
+fn answer_mask(line: &str) -> u32 {
+    // bit i is set if the letter 'a' + i was answered
+    line.bytes()
+        .filter(u8::is_ascii_lowercase)
+        .fold(0, |mask, b| mask | 1 << (b - b'a'))
 }
 
-fn part2(lines: impl Iterator<Item = impl AsRef<str>>) -> usize {
-    let mut current = AHashSet::new();
-    let mut question_sum = 0;
-    let mut is_first = true;
-    for line_ref in lines {
-        let line = line_ref.as_ref();
-        if line.is_empty() {
-            question_sum += current.len();
-            current.clear();
-            is_first = true;
-        } else if is_first {
-            for c in line.chars() {
-                current.insert(c);
+fn groups(lines: impl Iterator<Item = impl AsRef<str>>) -> impl Iterator<Item = Vec<u32>> {
+    let mut lines = lines;
+    std::iter::from_fn(move || {
+        let mut group = Vec::new();
+        for line_ref in lines.by_ref() {
+            let line = line_ref.as_ref();
+            if !line.is_empty() {
+                group.push(answer_mask(line));
+            } else if !group.is_empty() {
+                return Some(group);
             }
-
-            is_first = false;
-        } else {
-            current.retain(|&c| line.contains(c));
         }
-    }
 
-    question_sum + current.len()
+        Some(group).filter(|g| !g.is_empty())
+    })
+}
+
+fn count_answers(lines: impl Iterator<Item = impl AsRef<str>>) -> (u32, u32) {
+    groups(lines).fold((0, 0), |(anyone, everyone), group| {
+        let any_mask = group.iter().fold(0, |acc, mask| acc | mask);
+        let all_mask = group.iter().fold(!0, |acc, mask| acc & mask);
+        (
+            anyone + any_mask.count_ones(),
+            everyone + all_mask.count_ones(),
+        )
+    })
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
@@ -58,8 +49,9 @@ fn run() -> Result<(), Box<dyn Error>> {
             .collect::<Result<Vec<_>, _>>()?
     };
 
-    println!("Part 1: sum = {}", part1(lines.iter()));
-    println!("Part 2: sum = {}", part2(lines.iter()));
+    let (anyone, everyone) = count_answers(lines.iter());
+    println!("Part 1: sum = {}", anyone);
+    println!("Part 2: sum = {}", everyone);
 
     Ok(())
 }
@@ -76,7 +68,9 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{part1, part2};
+    use ahash::AHashSet;
+
+    use super::{count_answers, groups};
 
     const EXAMPLE: &str = r"abc
 
@@ -96,13 +90,79 @@ b";
 
     #[test]
     fn sum_test() {
-        let result = part1(EXAMPLE.lines());
+        let (result, _) = count_answers(EXAMPLE.lines());
         assert_eq!(result, 11);
     }
 
     #[test]
     fn all_test() {
-        let result = part2(EXAMPLE.lines());
+        let (_, result) = count_answers(EXAMPLE.lines());
         assert_eq!(result, 6);
     }
+
+    #[test]
+    fn groups_test() {
+        let member_counts = groups(EXAMPLE.lines())
+            .map(|group| group.len())
+            .collect::<Vec<_>>();
+        assert_eq!(member_counts, vec![1, 3, 2, 4, 1]);
+
+        let first = groups(EXAMPLE.lines()).next().unwrap();
+        assert_eq!(first, vec![0b111]);
+        assert_eq!(groups("\n\nab\n\n\n".lines()).count(), 1);
+    }
+
+    fn part1_hashset(lines: impl Iterator<Item = impl AsRef<str>>) -> usize {
+        let mut current = AHashSet::new();
+        let mut question_sum = 0;
+        for line_ref in lines {
+            let line = line_ref.as_ref();
+            if line.is_empty() {
+                question_sum += current.len();
+                current.clear();
+            } else {
+                current.extend(line.chars());
+            }
+        }
+
+        question_sum + current.len()
+    }
+
+    fn part2_hashset(lines: impl Iterator<Item = impl AsRef<str>>) -> usize {
+        let mut current = AHashSet::new();
+        let mut question_sum = 0;
+        let mut is_first = true;
+        for line_ref in lines {
+            let line = line_ref.as_ref();
+            if line.is_empty() {
+                question_sum += current.len();
+                current.clear();
+                is_first = true;
+            } else if is_first {
+                current.extend(line.chars());
+                is_first = false;
+            } else {
+                current.retain(|&c| line.contains(c));
+            }
+        }
+
+        question_sum + current.len()
+    }
+
+    #[test]
+    fn bitmask_matches_hashset() {
+        let inputs = [
+            EXAMPLE,
+            "abcdefghijklmnopqrstuvwxyz\nzyxwvutsrqponmlkjihgfedcba",
+            "az\nza\nz\n\nqwerty\nasdfgh\n",
+            "abc\n\n\n\nbcd\ncde\n\n",
+            "m\n\nmn\nnm\n\nxyz\nyz\nz",
+        ];
+
+        for input in inputs.iter() {
+            let (anyone, everyone) = count_answers(input.lines());
+            assert_eq!(anyone as usize, part1_hashset(input.lines()));
+            assert_eq!(everyone as usize, part2_hashset(input.lines()));
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use std::{
+    collections::VecDeque,
     error::Error,
+    fmt,
     fs::File,
     io::{BufRead, BufReader},
     path::PathBuf,
@@ -13,7 +15,24 @@ use rule::Rule;
 
 const BAG_TYPE: &str = "shiny gold";
 
-fn part1(rules: &[Rule]) -> usize {
+#[derive(Debug, PartialEq)]
+enum Day7Error {
+    CyclicRules,
+    UnknownColor(String),
+}
+
+impl fmt::Display for Day7Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Day7Error::CyclicRules => write!(f, "Containment rules contain a cycle"),
+            Day7Error::UnknownColor(color) => write!(f, "No rule for bag color {:?}", color),
+        }
+    }
+}
+
+impl Error for Day7Error {}
+
+fn containers(rules: &[Rule]) -> AHashMap<&str, Vec<&str>> {
     let mut nodes = AHashMap::with_capacity(rules.len());
     for rule in rules {
         for (_, color) in rule.bag_list() {
@@ -30,9 +49,14 @@ fn part1(rules: &[Rule]) -> usize {
         }
     }
 
+    nodes
+}
+
+fn part1(rules: &[Rule], target: &str) -> usize {
+    let nodes = containers(rules);
     let mut visited = AHashSet::with_capacity(nodes.len());
     let mut todo = Vec::with_capacity(nodes.len());
-    todo.push(BAG_TYPE);
+    todo.push(target);
 
     let mut total = 0;
     while let Some(key) = todo.pop() {
@@ -46,18 +70,87 @@ fn part1(rules: &[Rule]) -> usize {
     total - 1
 }
 
-fn count_node(nodes: &AHashMap<&str, &[(i32, String)]>, node: &str) -> usize {
-    nodes.get(node).unwrap().iter().fold(0, |acc, (n, t)| {
-        acc + *n as usize * (1 + count_node(nodes, t))
-    })
+fn path_to(rules: &[Rule], from: &str, to: &str) -> Option<Vec<String>> {
+    // breadth-first search outwards from the target, recording the bag each color was reached from
+    let nodes = containers(rules);
+    let mut inner_of = AHashMap::<&str, &str>::with_capacity(nodes.len());
+    let mut todo = VecDeque::new();
+    todo.push_back(*nodes.get_key_value(to)?.0);
+    while let Some(key) = todo.pop_front() {
+        if key == from {
+            let mut path = vec![key.to_string()];
+            let mut current = key;
+            while let Some(&inner) = inner_of.get(current) {
+                path.push(inner.to_string());
+                current = inner;
+            }
+            return Some(path);
+        }
+
+        for &outer in &nodes[key] {
+            if outer != to && !inner_of.contains_key(outer) {
+                inner_of.insert(outer, key);
+                todo.push_back(outer);
+            }
+        }
+    }
+
+    None
+}
+
+fn check_acyclic<'a>(
+    nodes: &AHashMap<&'a str, &'a [(i32, String)]>,
+    node: &'a str,
+    finished: &mut AHashMap<&'a str, bool>,
+) -> Result<(), Day7Error> {
+    // nodes map to false while they are on the current path, and true once fully explored
+    match finished.get(node) {
+        Some(true) => return Ok(()),
+        Some(false) => return Err(Day7Error::CyclicRules),
+        None => (),
+    }
+
+    finished.insert(node, false);
+    for (_, t) in nodes.get(node).copied().unwrap_or_default() {
+        check_acyclic(nodes, t, finished)?;
+    }
+
+    finished.insert(node, true);
+    Ok(())
 }
 
-pub fn part2(lines: &[Rule]) -> usize {
+fn count_node<'a>(
+    nodes: &AHashMap<&'a str, &'a [(i32, String)]>,
+    node: &'a str,
+    cache: &mut AHashMap<&'a str, usize>,
+) -> usize {
+    if let Some(&count) = cache.get(node) {
+        return count;
+    }
+
+    let count = nodes.get(node).unwrap().iter().fold(0, |acc, (n, t)| {
+        acc + *n as usize * (1 + count_node(nodes, t, cache))
+    });
+    cache.insert(node, count);
+    count
+}
+
+fn part2(lines: &[Rule], target: &str) -> Result<usize, Day7Error> {
     let map = lines.iter().map(|r| (r.color(), r.bag_list())).collect();
-    count_node(&map, BAG_TYPE)
+    check_acyclic(&map, target, &mut AHashMap::with_capacity(lines.len()))?;
+    let mut cache = AHashMap::with_capacity(lines.len());
+    Ok(count_node(&map, target, &mut cache))
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
+    // the color may be given as a single argument or split over several, e.g. dark red
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let target = if args.is_empty() {
+        BAG_TYPE.to_owned()
+    } else {
+        args.join(" ")
+    };
+
     let rules = {
         let path = ["data", "day07", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
@@ -68,8 +161,20 @@ fn run() -> Result<(), Box<dyn Error>> {
         rules
     };
 
-    println!("Part 1: {} valid bags", part1(&rules));
-    println!("Part 2: {} contained bags", part2(&rules));
+    if !rules.iter().any(|r: &Rule| r.color() == target) {
+        return Err(Day7Error::UnknownColor(target).into());
+    }
+
+    println!("Part 1: {} valid bags", part1(&rules, &target));
+    println!("Part 2: {} contained bags", part2(&rules, &target)?);
+
+    // explain how the given color relates to shiny gold, in whichever direction a chain exists
+    if target != BAG_TYPE {
+        match path_to(&rules, BAG_TYPE, &target).or_else(|| path_to(&rules, &target, BAG_TYPE)) {
+            Some(path) => println!("Path: {}", path.join(" -> ")),
+            None => println!("No path between {} and {}", BAG_TYPE, target),
+        }
+    }
 
     Ok(())
 }
@@ -86,7 +191,7 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{part1, part2};
+    use super::{part1, part2, path_to, Day7Error, BAG_TYPE};
 
     const RULES1: &str = r"light red bags contain 1 bright white bag, 2 muted yellow bags.
 dark orange bags contain 3 bright white bags, 4 muted yellow bags.
@@ -112,7 +217,7 @@ dark violet bags contain no other bags.";
             .lines()
             .map(|s| s.parse().unwrap())
             .collect::<Vec<_>>();
-        let result = part1(&rules);
+        let result = part1(&rules, BAG_TYPE);
 
         assert_eq!(result, 4);
     }
@@ -124,7 +229,7 @@ dark violet bags contain no other bags.";
             .rev()
             .map(|s| s.parse().unwrap())
             .collect::<Vec<_>>();
-        let result = part1(&rules);
+        let result = part1(&rules, BAG_TYPE);
 
         assert_eq!(result, 4);
     }
@@ -135,7 +240,7 @@ dark violet bags contain no other bags.";
             .lines()
             .map(|s| s.parse().unwrap())
             .collect::<Vec<_>>();
-        let result = part2(&rules);
+        let result = part2(&rules, BAG_TYPE).unwrap();
         assert_eq!(result, 32);
     }
 
@@ -146,7 +251,7 @@ dark violet bags contain no other bags.";
             .rev()
             .map(|s| s.parse().unwrap())
             .collect::<Vec<_>>();
-        let result = part2(&rules);
+        let result = part2(&rules, BAG_TYPE).unwrap();
         assert_eq!(result, 32);
     }
 
@@ -156,7 +261,7 @@ dark violet bags contain no other bags.";
             .lines()
             .map(|s| s.parse().unwrap())
             .collect::<Vec<_>>();
-        let result = part2(&rules);
+        let result = part2(&rules, BAG_TYPE).unwrap();
         assert_eq!(result, 126);
     }
 
@@ -167,7 +272,88 @@ dark violet bags contain no other bags.";
             .rev()
             .map(|s| s.parse().unwrap())
             .collect::<Vec<_>>();
-        let result = part2(&rules);
+        let result = part2(&rules, BAG_TYPE).unwrap();
         assert_eq!(result, 126);
     }
+
+    #[test]
+    fn part2_diamond_test() {
+        // each level holds one of each bag on the next level, so naive recursion visits 2^40 paths
+        const LEVELS: usize = 40;
+        let name = |level: usize, side: &str| {
+            let letter = |n: usize| (b'a' + n as u8) as char;
+            format!("{} {}{}", side, letter(level / 26), letter(level % 26))
+        };
+
+        let mut lines = vec![format!(
+            "shiny gold bags contain 1 {} bag, 1 {} bag.",
+            name(0, "left"),
+            name(0, "right")
+        )];
+        for level in 0..LEVELS {
+            for &side in &["left", "right"] {
+                if level + 1 < LEVELS {
+                    lines.push(format!(
+                        "{} bags contain 1 {} bag, 1 {} bag.",
+                        name(level, side),
+                        name(level + 1, "left"),
+                        name(level + 1, "right")
+                    ));
+                } else {
+                    lines.push(format!("{} bags contain no other bags.", name(level, side)));
+                }
+            }
+        }
+
+        let rules = lines.iter().map(|s| s.parse().unwrap()).collect::<Vec<_>>();
+        let result = part2(&rules, BAG_TYPE).unwrap();
+        assert_eq!(result, (1 << (LEVELS + 1)) - 2);
+    }
+
+    #[test]
+    fn part2_cycle_test() {
+        let rules = [
+            "shiny gold bags contain 1 dark red bag.",
+            "dark red bags contain 2 dark blue bags.",
+            "dark blue bags contain 1 dark red bag, 3 faded blue bags.",
+            "faded blue bags contain no other bags.",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect::<Vec<_>>();
+        assert_eq!(part2(&rules, BAG_TYPE), Err(Day7Error::CyclicRules));
+    }
+
+    #[test]
+    fn path_to_test() {
+        let rules = RULES1
+            .lines()
+            .map(|s| s.parse().unwrap())
+            .collect::<Vec<_>>();
+        let path = path_to(&rules, "light red", "shiny gold").unwrap();
+        assert_eq!(path.first().unwrap(), "light red");
+        assert_eq!(path.last().unwrap(), "shiny gold");
+        assert_eq!(path.len(), 3);
+        for pair in path.windows(2) {
+            let rule = rules.iter().find(|r| r.color() == pair[0]).unwrap();
+            assert!(rule.bag_list().iter().any(|(_, color)| *color == pair[1]));
+        }
+
+        assert_eq!(
+            path_to(&rules, "shiny gold", "shiny gold"),
+            Some(vec!["shiny gold".to_owned()])
+        );
+        assert_eq!(path_to(&rules, "faded blue", "shiny gold"), None);
+        assert_eq!(path_to(&rules, "light red", "plaid purple"), None);
+    }
+
+    #[test]
+    fn dark_red_target_test() {
+        let rules = RULES2
+            .lines()
+            .map(|s| s.parse().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(part1(&rules, "dark red"), 1);
+        assert_eq!(part2(&rules, "dark red").unwrap(), 62);
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     error::Error,
     fmt,
     fs::File,
@@ -13,6 +14,7 @@ use bitvec::prelude::*;
 enum Day8Error {
     ParseError,
     NoSolution,
+    Fault(usize),
 }
 
 impl fmt::Display for Day8Error {
@@ -20,6 +22,7 @@ impl fmt::Display for Day8Error {
         match self {
             Self::ParseError => write!(f, "Parse error"),
             Self::NoSolution => write!(f, "No solution found"),
+            Self::Fault(pc) => write!(f, "Program faulted at instruction {}", pc),
         }
     }
 }
@@ -31,6 +34,7 @@ enum Instruction {
     Acc(i32),
     Jmp(i32),
     Nop(i32),
+    Out(i32),
 }
 
 impl FromStr for Instruction {
@@ -46,6 +50,7 @@ impl FromStr for Instruction {
             "acc" => Ok(Instruction::Acc(value)),
             "jmp" => Ok(Instruction::Jmp(value)),
             "nop" => Ok(Instruction::Nop(value)),
+            "out" => Ok(Instruction::Out(value)),
             _ => Err(Day8Error::ParseError),
         }
     }
@@ -55,38 +60,85 @@ impl FromStr for Instruction {
 enum ProgramResult {
     Terminate(i32),
     Loop(i32),
+    Fault(usize),
 }
 
-fn execute(program: &[Instruction]) -> ProgramResult {
-    let mut accumulator = 0;
-    let mut counter = 0;
-    let mut visited = bitvec![0; program.len()];
-    while counter < program.len() {
-        if visited[counter] {
-            return ProgramResult::Loop(accumulator);
-        }
-        visited.set(counter, true);
-        match program[counter] {
-            Instruction::Acc(delta) => {
-                accumulator += delta;
-                counter += 1;
+#[derive(Debug, PartialEq)]
+enum StepResult {
+    Running,
+    Halted,
+    OutOfBounds,
+    Fault,
+}
+
+#[derive(Debug, Default)]
+struct Vm {
+    acc: i32,
+    pc: usize,
+    output: Vec<i32>,
+}
+
+impl Vm {
+    fn step(&mut self, program: &[Instruction]) -> StepResult {
+        let instruction = match program.get(self.pc) {
+            Some(instruction) => instruction,
+            None if self.pc == program.len() => return StepResult::Halted,
+            None => return StepResult::OutOfBounds,
+        };
+
+        match instruction {
+            Instruction::Acc(delta) => self.acc += delta,
+            Instruction::Out(value) => self.output.push(*value),
+            _ => (),
+        }
+
+        match jump_target(self.pc, instruction) {
+            Some(target) => {
+                self.pc = target;
+                match target.cmp(&program.len()) {
+                    Ordering::Less => StepResult::Running,
+                    Ordering::Equal => StepResult::Halted,
+                    Ordering::Greater => StepResult::OutOfBounds,
+                }
             }
-            Instruction::Jmp(delta) if delta >= 0 => counter += delta as usize,
-            Instruction::Jmp(delta) => counter -= delta.abs() as usize,
-            Instruction::Nop(_) => counter += 1,
+            None => StepResult::Fault,
+        }
+    }
+}
+
+fn execute_traced(program: &[Instruction]) -> (ProgramResult, Vec<usize>) {
+    let mut vm = Vm::default();
+    let mut visited = bitvec![0; program.len()];
+    let mut trace = Vec::new();
+    while vm.pc < program.len() {
+        if visited[vm.pc] {
+            return (ProgramResult::Loop(vm.acc), trace);
+        }
+        visited.set(vm.pc, true);
+        trace.push(vm.pc);
+        let pc = vm.pc;
+        match vm.step(program) {
+            StepResult::Running => (),
+            StepResult::Fault => return (ProgramResult::Fault(pc), trace),
+            StepResult::Halted | StepResult::OutOfBounds => break,
         }
     }
 
-    ProgramResult::Terminate(accumulator)
+    (ProgramResult::Terminate(vm.acc), trace)
+}
+
+fn execute(program: &[Instruction]) -> ProgramResult {
+    execute_traced(program).0
 }
 
 fn part1(program: &[Instruction]) -> Result<(), Day8Error> {
-    match execute(&program) {
+    match execute(program) {
         ProgramResult::Loop(result) => {
             println!("Part 1: accumulator = {}", result);
             Ok(())
         }
-        _ => Err(Day8Error::NoSolution),
+        ProgramResult::Fault(pc) => Err(Day8Error::Fault(pc)),
+        ProgramResult::Terminate(_) => Err(Day8Error::NoSolution),
     }
 }
 
@@ -104,10 +156,11 @@ fn patch(instruction: &mut Instruction) -> bool {
     }
 }
 
+#[cfg(test)]
 fn execute_patched(patched: &mut [Instruction]) -> Result<i32, Day8Error> {
     for p in 0..patched.len() {
         if patch(&mut patched[p]) {
-            match execute(&patched) {
+            match execute(patched) {
                 ProgramResult::Terminate(result) => return Ok(result),
                 _ => {
                     patch(&mut patched[p]);
@@ -119,14 +172,66 @@ fn execute_patched(patched: &mut [Instruction]) -> Result<i32, Day8Error> {
     Err(Day8Error::NoSolution)
 }
 
-fn part2(program: &mut [Instruction]) -> Result<(), Day8Error> {
-    let result = execute_patched(program)?;
+fn jump_target(counter: usize, instruction: &Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::Jmp(delta) if *delta >= 0 => counter.checked_add(*delta as usize),
+        Instruction::Jmp(delta) => counter.checked_sub(delta.unsigned_abs() as usize),
+        _ => Some(counter + 1),
+    }
+}
+
+fn execute_patched_fast(program: &[Instruction]) -> Result<i32, Day8Error> {
+    // find every instruction from which the unpatched program runs off the end
+    let mut sources = vec![Vec::new(); program.len()];
+    let mut todo = Vec::new();
+    for (counter, instruction) in program.iter().enumerate() {
+        match jump_target(counter, instruction) {
+            Some(target) if target < program.len() => sources[target].push(counter),
+            Some(_) => todo.push(counter),
+            None => (),
+        }
+    }
+
+    let mut terminates = bitvec![0; program.len()];
+    while let Some(counter) = todo.pop() {
+        if !terminates[counter] {
+            terminates.set(counter, true);
+            todo.extend_from_slice(&sources[counter]);
+        }
+    }
+
+    // a successful patch must be on the original path and flip into an instruction that
+    // terminates, so only those candidates need to be executed
+    let (_, mut trace) = execute_traced(program);
+    trace.sort_unstable();
+    let mut patched = program.to_vec();
+    for counter in trace {
+        if !patch(&mut patched[counter]) {
+            continue;
+        }
+
+        let candidate = jump_target(counter, &patched[counter])
+            .is_some_and(|target| target >= program.len() || terminates[target]);
+        if candidate {
+            if let ProgramResult::Terminate(result) = execute(&patched) {
+                return Ok(result);
+            }
+        }
+
+        patch(&mut patched[counter]);
+    }
+
+    Err(Day8Error::NoSolution)
+}
+
+fn part2(program: &[Instruction]) -> Result<(), Day8Error> {
+    let result = execute_patched_fast(program)?;
     println!("Part 2: accumulator = {}", result);
     Ok(())
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
-    let mut program = {
+    let program = {
         let path = ["data", "day08", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
         let mut program = Vec::new();
@@ -137,7 +242,7 @@ fn run() -> Result<(), Box<dyn Error>> {
         program
     };
     part1(&program)?;
-    part2(&mut program)?;
+    part2(&program)?;
     Ok(())
 }
 
@@ -153,7 +258,10 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{execute, execute_patched, Instruction, ProgramResult};
+    use super::{
+        execute, execute_patched, execute_patched_fast, execute_traced, Instruction, ProgramResult,
+        StepResult, Vm,
+    };
 
     const EXAMPLE: [Instruction; 9] = [
         Instruction::Nop(0),
@@ -182,5 +290,90 @@ mod test {
         let mut program = EXAMPLE.clone();
         let result = execute_patched(&mut program).unwrap();
         assert_eq!(result, 8);
+        assert_eq!(execute_patched_fast(&EXAMPLE).unwrap(), 8);
+    }
+
+    #[test]
+    fn patched_fast_test() {
+        use Instruction::{Acc, Jmp, Nop};
+
+        // the fixes are, in turn, a jmp changed to a nop, a nop changed to a jmp, and a jmp whose
+        // loop runs over a backward nop that must be left alone
+        let programs: [(&[Instruction], i32); 3] = [
+            (&EXAMPLE, 8),
+            (&[Acc(2), Nop(3), Jmp(-2), Jmp(-3), Acc(5)], 7),
+            (&[Acc(1), Nop(-1), Acc(2), Jmp(-2), Acc(4)], 7),
+        ];
+
+        for &(program, expected) in programs.iter() {
+            assert_eq!(execute_patched(&mut program.to_vec()).unwrap(), expected);
+            assert_eq!(execute_patched_fast(program).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn trace_test() {
+        let (result, trace) = execute_traced(&EXAMPLE);
+        assert!(matches!(result, ProgramResult::Loop(5)));
+        assert_eq!(trace, vec![0, 1, 2, 6, 7, 3, 4]);
+
+        let mut program = EXAMPLE.clone();
+        program[7] = Instruction::Nop(-4);
+        let (result, trace) = execute_traced(&program);
+        assert!(matches!(result, ProgramResult::Terminate(8)));
+        assert_eq!(trace, vec![0, 1, 2, 6, 7, 8]);
+    }
+
+    #[test]
+    fn step_test() {
+        let expected = [(1, 0), (2, 1), (6, 1), (7, 2), (3, 2), (4, 5), (1, 5)];
+        let mut vm = Vm::default();
+        for &(pc, acc) in &expected {
+            assert_eq!(vm.step(&EXAMPLE), StepResult::Running);
+            assert_eq!((vm.pc, vm.acc), (pc, acc));
+        }
+
+        let mut vm = Vm {
+            pc: 8,
+            ..Vm::default()
+        };
+        assert_eq!(vm.step(&EXAMPLE), StepResult::Halted);
+        assert_eq!((vm.pc, vm.acc), (9, 6));
+        assert_eq!(vm.step(&EXAMPLE), StepResult::Halted);
+
+        let mut vm = Vm {
+            pc: 2,
+            ..Vm::default()
+        };
+        assert_eq!(vm.step(&EXAMPLE[..5]), StepResult::OutOfBounds);
+        assert_eq!(vm.pc, 6);
+    }
+
+    #[test]
+    fn fault_test() {
+        let program = [Instruction::Acc(1), Instruction::Jmp(-2)];
+        let (result, trace) = execute_traced(&program);
+        assert!(matches!(result, ProgramResult::Fault(1)), "{:?}", result);
+        assert_eq!(trace, vec![0, 1]);
+
+        let mut vm = Vm {
+            pc: 1,
+            ..Vm::default()
+        };
+        assert_eq!(vm.step(&program), StepResult::Fault);
+        assert_eq!(vm.pc, 1);
+    }
+
+    #[test]
+    fn out_test() {
+        let program = ["out +3", "acc +2", "out -7", "nop +0"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect::<Vec<Instruction>>();
+        let mut vm = Vm::default();
+        while vm.step(&program) == StepResult::Running {}
+        assert_eq!(vm.output, vec![3, -7]);
+        assert_eq!((vm.pc, vm.acc), (4, 2));
+        assert!(matches!(execute(&program), ProgramResult::Terminate(2)));
     }
 }
@@ -1,5 +1,6 @@
 use std::{
     cmp::{Ord, Ordering},
+    collections::BTreeMap,
     error::Error,
     fmt,
     fs::File,
@@ -19,6 +20,27 @@ impl fmt::Display for NotFoundError {
 
 impl Error for NotFoundError {}
 
+#[derive(Debug)]
+struct PreambleError {
+    preamble_size: usize,
+    length: usize,
+}
+
+impl fmt::Display for PreambleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid preamble size {} for sequence of length {}",
+            self.preamble_size, self.length
+        )
+    }
+}
+
+impl Error for PreambleError {}
+
+const DEFAULT_PREAMBLE_SIZE: usize = 25;
+
+#[cfg(test)]
 fn find_pair<T>(numbers: &[T], target: T) -> Option<(T, T)>
 where
     T: Add<Output = T> + Copy + Ord,
@@ -42,6 +64,7 @@ where
     }
 }
 
+#[cfg(test)]
 fn find_incorrect<T>(sequence: &[T], preamble_size: usize) -> Option<T>
 where
     T: Add<Output = T> + Copy + Default + Ord,
@@ -61,41 +84,112 @@ where
         .next()
 }
 
-fn part1(sequence: &[i64]) -> Result<i64, NotFoundError> {
-    let result = find_incorrect(sequence, 25).ok_or(NotFoundError {})?;
+fn has_pair<T>(multiset: &BTreeMap<T, usize>, target: T) -> bool
+where
+    T: Add<Output = T> + Copy + Ord,
+{
+    // two-pointer search over the distinct keys, where a key may only pair with itself if it
+    // occurs more than once
+    let mut it = multiset.iter();
+    let mut low = it.next();
+    let mut high = it.next_back();
+    while let Some((&low_value, &low_count)) = low {
+        let (&high_value, _) = match high {
+            Some(entry) => entry,
+            None => return low_count > 1 && low_value + low_value == target,
+        };
+
+        match (low_value + high_value).cmp(&target) {
+            Ordering::Equal => return true,
+            Ordering::Less => {
+                low = it.next();
+                if low.is_none() {
+                    low = high.take();
+                }
+            }
+            Ordering::Greater => high = it.next_back(),
+        }
+    }
+
+    false
+}
+
+fn find_incorrect_fast<T>(sequence: &[T], preamble_size: usize) -> Option<T>
+where
+    T: Add<Output = T> + Copy + Ord,
+{
+    let mut multiset = BTreeMap::new();
+    for &value in sequence.iter().take(preamble_size) {
+        *multiset.entry(value).or_insert(0) += 1;
+    }
+
+    for (i, &target) in sequence.iter().enumerate().skip(preamble_size) {
+        if !has_pair(&multiset, target) {
+            return Some(target);
+        }
+
+        *multiset.entry(target).or_insert(0) += 1;
+        let outgoing = sequence[i - preamble_size];
+        let count = multiset.get_mut(&outgoing).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            multiset.remove(&outgoing);
+        }
+    }
+
+    None
+}
+
+fn part1(sequence: &[i64], preamble_size: usize) -> Result<i64, Box<dyn Error>> {
+    // a pair needs at least two numbers, and at least one number must follow the preamble
+    if preamble_size < 2 || preamble_size >= sequence.len() {
+        return Err(PreambleError {
+            preamble_size,
+            length: sequence.len(),
+        }
+        .into());
+    }
+
+    let result = find_incorrect_fast(sequence, preamble_size).ok_or(NotFoundError {})?;
     println!("Part1: result = {}", result);
     Ok(result)
 }
 
-fn find_contiguous<T>(sequence: &[T], target: T) -> Option<T>
+fn find_contiguous_range<T>(sequence: &[T], target: T) -> Option<(usize, usize)>
 where
-    T: Add<Output = T> + AddAssign<T> + Copy + Ord,
+    T: AddAssign<T> + Copy + Ord,
 {
-    let mut subsequence = sequence;
-    while !subsequence.is_empty() {
-        let mut sum = subsequence[0];
-        let mut min_element = sum;
-        let mut max_element = sum;
-        for &element in &subsequence[1..] {
+    for start in 0..sequence.len() {
+        let mut sum = sequence[start];
+        let mut end = start;
+        for (i, &element) in sequence.iter().enumerate().skip(start + 1) {
             if sum >= target {
                 break;
             }
 
             sum += element;
-            min_element = std::cmp::min(min_element, element);
-            max_element = std::cmp::max(max_element, element);
+            end = i;
         }
 
         if sum == target {
-            return Some(min_element + max_element);
+            return Some((start, end));
         }
-
-        subsequence = &subsequence[1..];
     }
 
     None
 }
 
+fn find_contiguous<T>(sequence: &[T], target: T) -> Option<T>
+where
+    T: Add<Output = T> + AddAssign<T> + Copy + Ord,
+{
+    let (start, end) = find_contiguous_range(sequence, target)?;
+    let subsequence = &sequence[start..=end];
+    let min_element = *subsequence.iter().min()?;
+    let max_element = *subsequence.iter().max()?;
+    Some(min_element + max_element)
+}
+
 fn part2(sequence: &[i64], target: i64) -> Result<(), NotFoundError> {
     let result = find_contiguous(sequence, target).ok_or(NotFoundError {})?;
     println!("Part 2: result = {}", result);
@@ -103,6 +197,11 @@ fn part2(sequence: &[i64], target: i64) -> Result<(), NotFoundError> {
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
+    let preamble_size = match std::env::args().nth(1) {
+        Some(arg) => arg.parse()?,
+        None => DEFAULT_PREAMBLE_SIZE,
+    };
+
     let source = {
         let path = ["data", "day09", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
@@ -113,7 +212,7 @@ fn run() -> Result<(), Box<dyn Error>> {
 
         source
     };
-    let target = part1(&source)?;
+    let target = part1(&source, preamble_size)?;
     part2(&source, target)?;
     Ok(())
 }
@@ -130,7 +229,9 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{find_contiguous, find_incorrect};
+    use super::{
+        find_contiguous, find_contiguous_range, find_incorrect, find_incorrect_fast, part1,
+    };
 
     const EXAMPLE_SEQUENCE: [i32; 20] = [
         35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
@@ -147,4 +248,53 @@ mod test {
         let result = find_contiguous(&EXAMPLE_SEQUENCE, 127).unwrap();
         assert_eq!(result, 62);
     }
+
+    #[test]
+    fn preamble_size_test() {
+        let sequence = EXAMPLE_SEQUENCE
+            .iter()
+            .map(|&x| i64::from(x))
+            .collect::<Vec<_>>();
+        assert_eq!(part1(&sequence, 5).unwrap(), 127);
+
+        let error = part1(&sequence, 20).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid preamble size 20 for sequence of length 20"
+        );
+        assert!(part1(&sequence, 1).is_err());
+    }
+
+    #[test]
+    fn find_incorrect_fast_test() {
+        for preamble_size in 2..=5 {
+            assert_eq!(
+                find_incorrect_fast(&EXAMPLE_SEQUENCE, preamble_size),
+                find_incorrect(&EXAMPLE_SEQUENCE, preamble_size)
+            );
+        }
+
+        // a value may only pair with itself while it occurs twice in the window
+        let sequences: [(&[i64], usize, Option<i64>); 5] = [
+            (&[5, 5, 1, 10], 3, None),
+            (&[5, 1, 2, 10], 3, Some(10)),
+            (&[5, 5, 1, 6, 10], 3, Some(10)),
+            (&[-3, 4, 1, -2, 5], 3, None),
+            (&[1, 2], 2, None),
+        ];
+
+        for &(sequence, preamble_size, expected) in sequences.iter() {
+            assert_eq!(find_incorrect(sequence, preamble_size), expected);
+            assert_eq!(find_incorrect_fast(sequence, preamble_size), expected);
+        }
+    }
+
+    #[test]
+    fn contiguous_range_test() {
+        let (start, end) = find_contiguous_range(&EXAMPLE_SEQUENCE, 127).unwrap();
+        assert_eq!((start, end), (2, 5));
+        assert_eq!(EXAMPLE_SEQUENCE[start..=end], [15, 25, 47, 40]);
+        assert_eq!(EXAMPLE_SEQUENCE[start..=end].iter().sum::<i32>(), 127);
+        assert_eq!(find_contiguous_range(&EXAMPLE_SEQUENCE, 1), None);
+    }
 }
@@ -1,34 +1,76 @@
 use std::{
-    cmp::Reverse,
     error::Error,
+    fmt,
     fs::File,
     io::{BufRead, BufReader},
     path::PathBuf,
 };
 
-fn count_differences(source: &[i32]) -> usize {
-    let mut adapters = source.to_vec();
-    adapters.sort_unstable();
-    let mut previous = 0;
+use ahash::{AHashMap, AHashSet};
+
+#[derive(Debug)]
+struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid arguments: {}", self.0)
+    }
+}
+
+impl Error for ArgsError {}
+
+#[derive(Debug, PartialEq)]
+struct GapError {
+    from: i32,
+    to: i32,
+}
+
+impl fmt::Display for GapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Joltage gap from {} to {} is larger than 3",
+            self.from, self.to
+        )
+    }
+}
+
+impl Error for GapError {}
+
+fn build_chain(source: &[i32]) -> Result<Vec<i32>, GapError> {
+    let mut chain = Vec::with_capacity(source.len() + 2);
+    chain.push(0);
+    chain.extend_from_slice(source);
+    chain.sort_unstable();
+    chain.push(chain.last().unwrap() + 3);
+    match chain.windows(2).find(|pair| pair[1] - pair[0] > 3) {
+        Some(pair) => Err(GapError {
+            from: pair[0],
+            to: pair[1],
+        }),
+        None => Ok(chain),
+    }
+}
+
+fn count_differences(source: &[i32]) -> Result<usize, GapError> {
+    let chain = build_chain(source)?;
     let mut count1 = 0;
-    let mut count3 = 1;
-    for adapter in adapters {
-        match adapter - previous {
+    let mut count3 = 0;
+    for pair in chain.windows(2) {
+        match pair[1] - pair[0] {
             1 => count1 += 1,
             3 => count3 += 1,
             _ => (),
         }
-
-        previous = adapter;
     }
 
-    count1 * count3
+    Ok(count1 * count3)
 }
 
-fn count_ways(source: &[i32]) -> u64 {
-    let mut adapters = source.to_vec();
-    adapters.sort_unstable_by_key(|&a| Reverse(a));
-    adapters.push(0);
+fn count_ways(source: &[i32]) -> Result<u64, GapError> {
+    let mut adapters = build_chain(source)?;
+    adapters.pop();
+    adapters.reverse();
     let mut scores = Vec::with_capacity(adapters.len());
     scores.push(1);
     for &adapter in &adapters[1..] {
@@ -43,10 +85,49 @@ fn count_ways(source: &[i32]) -> u64 {
         scores.push(score);
     }
 
-    *scores.last().unwrap()
+    Ok(*scores.last().unwrap())
+}
+
+fn count_ways_from(
+    joltage: i32,
+    adapters: &AHashSet<i32>,
+    device: i32,
+    cache: &mut AHashMap<i32, u64>,
+) -> u64 {
+    if joltage + 3 == device {
+        return 1;
+    }
+
+    if let Some(&ways) = cache.get(&joltage) {
+        return ways;
+    }
+
+    let ways = (joltage + 1..=joltage + 3)
+        .filter(|next| adapters.contains(next))
+        .map(|next| count_ways_from(next, adapters, device, cache))
+        .sum();
+    cache.insert(joltage, ways);
+    ways
+}
+
+fn count_ways_memoized(source: &[i32]) -> u64 {
+    let adapters = source.iter().copied().collect::<AHashSet<_>>();
+    let device = source.iter().max().map_or(0, |&max| max) + 3;
+    let mut cache = AHashMap::with_capacity(source.len());
+    count_ways_from(0, &adapters, device, &mut cache)
+}
+
+// returns whether to count the arrangements with the memoized recursion
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<bool, ArgsError> {
+    match args.next() {
+        None => Ok(false),
+        Some(arg) if arg == "--memoized" && args.next().is_none() => Ok(true),
+        Some(arg) => Err(ArgsError(format!("unexpected argument {:?}", arg))),
+    }
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
+    let memoized = parse_args(std::env::args().skip(1))?;
     let adapters = {
         let path = ["data", "day10", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
@@ -58,8 +139,12 @@ fn run() -> Result<(), Box<dyn Error>> {
         adapters
     };
 
-    println!("Part 1: result = {}", count_differences(&adapters));
-    println!("Part 2: result = {}", count_ways(&adapters));
+    println!("Part 1: result = {}", count_differences(&adapters)?);
+    if memoized {
+        println!("Part 2: result = {}", count_ways_memoized(&adapters));
+    } else {
+        println!("Part 2: result = {}", count_ways(&adapters)?);
+    }
 
     Ok(())
 }
@@ -76,7 +161,9 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{count_differences, count_ways};
+    use super::{
+        build_chain, count_differences, count_ways, count_ways_memoized, parse_args, GapError,
+    };
 
     const EXAMPLE1: [i32; 11] = [16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
 
@@ -87,25 +174,73 @@ mod test {
 
     #[test]
     fn test_part1_small() {
-        let result = count_differences(&EXAMPLE1);
+        let result = count_differences(&EXAMPLE1).unwrap();
         assert_eq!(result, 35);
     }
 
     #[test]
     fn test_part1_large() {
-        let result = count_differences(&EXAMPLE2);
+        let result = count_differences(&EXAMPLE2).unwrap();
         assert_eq!(result, 220);
     }
 
     #[test]
     fn test_part2_small() {
-        let result = count_ways(&EXAMPLE1);
+        let result = count_ways(&EXAMPLE1).unwrap();
         assert_eq!(result, 8);
     }
 
     #[test]
     fn test_part2_large() {
-        let result = count_ways(&EXAMPLE2);
+        let result = count_ways(&EXAMPLE2).unwrap();
         assert_eq!(result, 19208);
     }
+
+    #[test]
+    fn test_build_chain() {
+        let chain = build_chain(&EXAMPLE1).unwrap();
+        assert_eq!(chain.first(), Some(&0));
+        assert_eq!(chain.last(), Some(&22));
+        assert_eq!(chain, vec![0, 1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19, 22]);
+    }
+
+    #[test]
+    fn test_count_ways_memoized() {
+        assert_eq!(count_ways_memoized(&EXAMPLE1), 8);
+        assert_eq!(
+            count_ways_memoized(&EXAMPLE1),
+            count_ways(&EXAMPLE1).unwrap()
+        );
+        assert_eq!(count_ways_memoized(&EXAMPLE2), 19208);
+        assert_eq!(
+            count_ways_memoized(&EXAMPLE2),
+            count_ways(&EXAMPLE2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gap_error() {
+        let expected = GapError { from: 1, to: 5 };
+        assert_eq!(build_chain(&[1, 5]), Err(expected));
+        let error = count_ways(&[1, 5]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Joltage gap from 1 to 5 is larger than 3"
+        );
+        assert!(count_differences(&[4]).is_err());
+    }
+
+    #[test]
+    fn parse_args_test() {
+        let args = |a: &[&str]| {
+            a.iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+        assert!(!parse_args(args(&[])).unwrap());
+        assert!(parse_args(args(&["--memoized"])).unwrap());
+        assert!(parse_args(args(&["--fast"])).is_err());
+        assert!(parse_args(args(&["--memoized", "x"])).is_err());
+    }
 }
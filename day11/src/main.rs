@@ -3,16 +3,24 @@ use std::{error::Error, fs::read_to_string, path::PathBuf};
 mod error;
 mod seating;
 
-use seating::SeatingPlan;
+use seating::{Rules, SeatingPlan};
 
 fn part1(mut plan: SeatingPlan) {
-    while plan.update() {}
-    println!("Part 1: occupied = {}", plan.occupied());
+    let rounds = plan.stabilize(Rules::ADJACENT);
+    println!(
+        "Part 1: occupied = {}, rounds = {}",
+        plan.occupied(),
+        rounds
+    );
 }
 
 fn part2(mut plan: SeatingPlan) {
-    while plan.update2() {}
-    println!("Part 2: occupied = {}", plan.occupied());
+    let rounds = plan.stabilize(Rules::LINE_OF_SIGHT);
+    println!(
+        "Part 2: occupied = {}, rounds = {}",
+        plan.occupied(),
+        rounds
+    );
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
@@ -20,6 +28,13 @@ fn run() -> Result<(), Box<dyn Error>> {
         let path = ["data", "day11", "input.txt"].iter().collect::<PathBuf>();
         read_to_string(path)?.parse::<SeatingPlan>()?
     };
+    // --show prints each generation of the part 1 rules until the plan stabilizes
+    if std::env::args().skip(1).any(|arg| arg == "--show") {
+        for (round, generation) in plan.clone().generations(Rules::ADJACENT).enumerate() {
+            println!("Round {}:\n{}\n", round, generation);
+        }
+    }
+
     part1(plan.clone());
     part2(plan);
     Ok(())
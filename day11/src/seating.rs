@@ -1,8 +1,11 @@
-use std::{cmp::min, fmt, str::FromStr};
+use std::{fmt, iter, str::FromStr};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use crate::error::Day11Error;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Seat {
     Empty,
     Unoccupied,
@@ -27,84 +30,100 @@ pub struct SeatingPlan {
     data2: Vec<Seat>,
 }
 
-fn count_line(line: &[Seat]) -> usize {
-    line.iter().filter(|s| s.is_occupied()).count()
-}
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
 
-fn scan_left(src: &[Seat], i: usize, x: usize) -> bool {
-    src[i - x..i]
-        .iter()
-        .rev()
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
+#[derive(Debug, Copy, Clone)]
+pub struct Rules {
+    pub crowd_threshold: usize,
+    pub line_of_sight: bool,
 }
 
-fn scan_right(src: &[Seat], i: usize, x: usize, width: usize) -> bool {
-    src[i + 1..i + width - x]
-        .iter()
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
-}
+impl Rules {
+    pub const ADJACENT: Rules = Rules {
+        crowd_threshold: 4,
+        line_of_sight: false,
+    };
 
-fn scan_up(src: &[Seat], i: usize, width: usize) -> bool {
-    src[..=i]
-        .iter()
-        .rev()
-        .step_by(width)
-        .skip(1)
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
+    pub const LINE_OF_SIGHT: Rules = Rules {
+        crowd_threshold: 5,
+        line_of_sight: true,
+    };
 }
 
-fn scan_down(src: &[Seat], i: usize, width: usize) -> bool {
-    src[i..]
-        .iter()
-        .step_by(width)
-        .skip(1)
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
-}
+fn sees_occupied(
+    src: &[Seat],
+    (width, height): (usize, usize),
+    (mut x, mut y): (usize, usize),
+    (dx, dy): (isize, isize),
+    line_of_sight: bool,
+) -> bool {
+    loop {
+        x = match x.checked_add_signed(dx) {
+            Some(x) if x < width => x,
+            _ => return false,
+        };
+        y = match y.checked_add_signed(dy) {
+            Some(y) if y < height => y,
+            _ => return false,
+        };
 
-fn scan_left_up(src: &[Seat], i: usize, x: usize, width: usize) -> bool {
-    src[..=i]
-        .iter()
-        .rev()
-        .step_by(width + 1)
-        .skip(1)
-        .take(x)
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
+        // without line of sight only the adjacent cell is considered
+        let seat = src[y * width + x];
+        if seat.is_seat() || !line_of_sight {
+            return seat.is_occupied();
+        }
+    }
 }
 
-fn scan_right_up(src: &[Seat], i: usize, x: usize, width: usize) -> bool {
-    src[..=i]
+fn next_seat(src: &[Seat], size: (usize, usize), i: usize, rules: Rules) -> Seat {
+    let current = src[i];
+    if !current.is_seat() {
+        return current;
+    }
+
+    let position = (i % size.0, i / size.0);
+    let occupied = DIRECTIONS
         .iter()
-        .rev()
-        .step_by(width - 1)
-        .skip(1)
-        .take(width - x - 1)
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
+        .filter(|&&direction| sees_occupied(src, size, position, direction, rules.line_of_sight))
+        .count();
+
+    match current {
+        Seat::Unoccupied if occupied == 0 => Seat::Occupied,
+        Seat::Occupied if occupied >= rules.crowd_threshold => Seat::Unoccupied,
+        _ => current,
+    }
 }
 
-fn scan_left_down(src: &[Seat], i: usize, x: usize, width: usize) -> bool {
-    src[i..]
-        .iter()
-        .step_by(width - 1)
-        .skip(1)
-        .take(x)
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
+#[cfg_attr(all(feature = "rayon", not(test)), allow(dead_code))]
+fn update_serial(src: &[Seat], dest: &mut [Seat], size: (usize, usize), rules: Rules) -> bool {
+    let mut modified = false;
+    for (i, seat) in dest.iter_mut().enumerate() {
+        *seat = next_seat(src, size, i, rules);
+        modified |= *seat != src[i];
+    }
+
+    modified
 }
 
-fn scan_right_down(src: &[Seat], i: usize, x: usize, width: usize) -> bool {
-    src[i..]
-        .iter()
-        .step_by(width + 1)
-        .skip(1)
-        .take(width - x - 1)
-        .find(|s| s.is_seat())
-        .map_or(false, Seat::is_occupied)
+#[cfg(feature = "rayon")]
+fn update_parallel(src: &[Seat], dest: &mut [Seat], size: (usize, usize), rules: Rules) -> bool {
+    // each destination cell only reads from the source buffer, so cells can be updated independently
+    dest.par_iter_mut()
+        .enumerate()
+        .map(|(i, seat)| {
+            *seat = next_seat(src, size, i, rules);
+            *seat != src[i]
+        })
+        .reduce(|| false, |a, b| a || b)
 }
 
 impl SeatingPlan {
@@ -120,100 +139,43 @@ impl SeatingPlan {
         self.current().iter().filter(|s| s.is_occupied()).count()
     }
 
-    pub fn update(&mut self) -> bool {
-        let (mut src, mut dest) = if self.state {
-            (self.data2.chunks(self.width), self.data1.iter_mut())
+    pub fn update(&mut self, rules: Rules) -> bool {
+        let size = (self.width, self.height);
+        let (src, dest) = if self.state {
+            (&self.data2, &mut self.data1)
         } else {
-            (self.data1.chunks(self.width), self.data2.iter_mut())
+            (&self.data1, &mut self.data2)
         };
 
-        let mut modified = false;
-
-        let mut prev: Option<&[Seat]> = None;
-        let mut curr = src.next();
-        let mut next = src.next();
-
-        while let Some(curr_line) = curr {
-            for x in 0..curr_line.len() {
-                let current = curr_line[x];
-                if !current.is_seat() {
-                    dest.next();
-                    continue;
-                }
-
-                let range = x.saturating_sub(1)..min(curr_line.len(), x + 2);
-                let occupied_neighbors = prev.map_or(0, |p| count_line(&p[range.clone()]))
-                    + count_line(&curr_line[range.clone()])
-                    + next.map_or(0, |n| count_line(&n[range]));
-
-                *dest.next().unwrap() = match current {
-                    Seat::Unoccupied if occupied_neighbors == 0 => {
-                        modified = true;
-                        Seat::Occupied
-                    }
-                    Seat::Occupied if occupied_neighbors >= 5 => {
-                        modified = true;
-                        Seat::Unoccupied
-                    }
-                    _ => current,
-                };
-            }
-
-            prev = curr;
-            curr = next;
-            next = src.next();
-        }
+        #[cfg(feature = "rayon")]
+        let modified = update_parallel(src, dest, size, rules);
+        #[cfg(not(feature = "rayon"))]
+        let modified = update_serial(src, dest, size, rules);
 
         self.state = !self.state;
         modified
     }
 
-    pub fn update2(&mut self) -> bool {
-        let (src, mut dest) = if self.state {
-            (&self.data2, self.data1.iter_mut())
-        } else {
-            (&self.data1, self.data2.iter_mut())
-        };
-
-        let mut modified = false;
+    pub fn stabilize(&mut self, rules: Rules) -> usize {
+        let mut rounds = 0;
+        while self.update(rules) {
+            rounds += 1;
+        }
 
-        for i in 0..src.len() {
-            let current = src[i];
-            if !current.is_seat() {
-                dest.next();
-                continue;
-            }
+        rounds
+    }
 
-            let x = i % self.width;
-            let visible = [
-                scan_left(src, i, x),
-                scan_right(src, i, x, self.width),
-                scan_up(src, i, self.width),
-                scan_down(src, i, self.width),
-                scan_left_up(src, i, x, self.width),
-                scan_right_up(src, i, x, self.width),
-                scan_left_down(src, i, x, self.width),
-                scan_right_down(src, i, x, self.width),
-            ]
-            .iter()
-            .filter(|v| **v)
-            .count();
-
-            *dest.next().unwrap() = match current {
-                Seat::Unoccupied if visible == 0 => {
-                    modified = true;
-                    Seat::Occupied
-                }
-                Seat::Occupied if visible >= 5 => {
-                    modified = true;
-                    Seat::Unoccupied
-                }
-                _ => current,
+    pub fn generations(self, rules: Rules) -> impl Iterator<Item = SeatingPlan> {
+        // yields the starting plan, then each plan that differs from its predecessor
+        let mut plan = Some(self);
+        iter::from_fn(move || {
+            let current = plan.take()?;
+            let mut next = current.clone();
+            if next.update(rules) {
+                plan = Some(next);
             }
-        }
-
-        self.state = !self.state;
-        modified
+            Some(current)
+        })
     }
 }
 
@@ -282,7 +244,9 @@ impl FromStr for SeatingPlan {
 
 #[cfg(test)]
 mod test {
-    use super::SeatingPlan;
+    #[cfg(feature = "rayon")]
+    use super::{update_parallel, update_serial};
+    use super::{Rules, SeatingPlan};
 
     const EXAMPLES_PART1: [&str; 6] = [
         r"L.LL.LL.LL
@@ -433,7 +397,7 @@ LLL###LLL#
     fn test_update() {
         let mut plan = EXAMPLES_PART1[0].parse::<SeatingPlan>().unwrap();
         for &expected in &EXAMPLES_PART1[1..] {
-            let was_updated = plan.update();
+            let was_updated = plan.update(Rules::ADJACENT);
             assert!(was_updated);
             assert_eq!(plan.to_string(), expected);
         }
@@ -443,7 +407,7 @@ LLL###LLL#
     fn test_no_update() {
         let expected = *EXAMPLES_PART1.last().unwrap();
         let mut plan = expected.parse::<SeatingPlan>().unwrap();
-        let was_updated = plan.update();
+        let was_updated = plan.update(Rules::ADJACENT);
         assert!(!was_updated);
         assert_eq!(plan.to_string(), expected);
     }
@@ -462,7 +426,7 @@ LLL###LLL#
     fn test_update2() {
         let mut plan = EXAMPLES_PART2[0].parse::<SeatingPlan>().unwrap();
         for &expected in &EXAMPLES_PART2[1..] {
-            let was_updated = plan.update2();
+            let was_updated = plan.update(Rules::LINE_OF_SIGHT);
             assert!(was_updated);
             assert_eq!(plan.to_string(), expected);
         }
@@ -472,7 +436,7 @@ LLL###LLL#
     fn test_no_update2() {
         let expected = *EXAMPLES_PART2.last().unwrap();
         let mut plan = expected.parse::<SeatingPlan>().unwrap();
-        let was_updated = plan.update2();
+        let was_updated = plan.update(Rules::LINE_OF_SIGHT);
         assert!(!was_updated);
         assert_eq!(plan.to_string(), expected);
     }
@@ -486,4 +450,79 @@ LLL###LLL#
             .unwrap();
         assert_eq!(plan.occupied(), 26);
     }
+
+    #[test]
+    fn test_custom_threshold() {
+        let rules = Rules {
+            crowd_threshold: 9,
+            line_of_sight: false,
+        };
+        let mut plan = EXAMPLES_PART1[0].parse::<SeatingPlan>().unwrap();
+        assert!(plan.update(rules));
+        assert_eq!(plan.to_string(), EXAMPLES_PART1[1]);
+        assert!(!plan.update(rules));
+        assert_eq!(plan.occupied(), 71);
+
+        for &(crowd_threshold, line_of_sight, expected) in
+            &[(5, false, 46), (6, false, 54), (4, true, 23), (6, true, 40)]
+        {
+            let rules = Rules {
+                crowd_threshold,
+                line_of_sight,
+            };
+            let mut plan = EXAMPLES_PART1[0].parse::<SeatingPlan>().unwrap();
+            while plan.update(rules) {}
+            assert_eq!(plan.occupied(), expected);
+        }
+    }
+
+    #[test]
+    fn test_stabilize() {
+        let mut plan = EXAMPLES_PART1[0].parse::<SeatingPlan>().unwrap();
+        assert_eq!(plan.stabilize(Rules::ADJACENT), 5);
+        assert_eq!(plan.to_string(), EXAMPLES_PART1[5]);
+        assert_eq!(plan.stabilize(Rules::ADJACENT), 0);
+
+        let mut plan = EXAMPLES_PART2[0].parse::<SeatingPlan>().unwrap();
+        assert_eq!(plan.stabilize(Rules::LINE_OF_SIGHT), 6);
+        assert_eq!(plan.occupied(), 26);
+    }
+
+    #[test]
+    fn test_generations() {
+        let plan = EXAMPLES_PART1[0].parse::<SeatingPlan>().unwrap();
+        let plans = plan.generations(Rules::ADJACENT).collect::<Vec<_>>();
+        let layouts = plans.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(layouts, EXAMPLES_PART1);
+        let occupied = plans.iter().map(|p| p.occupied()).collect::<Vec<_>>();
+        assert_eq!(occupied, vec![0, 71, 20, 51, 30, 37]);
+
+        let plan = EXAMPLES_PART2[0].parse::<SeatingPlan>().unwrap();
+        let occupied = plan
+            .generations(Rules::LINE_OF_SIGHT)
+            .map(|p| p.occupied())
+            .collect::<Vec<_>>();
+        assert_eq!(occupied, vec![0, 71, 7, 53, 18, 31, 26]);
+
+        let stable = EXAMPLES_PART1[5].parse::<SeatingPlan>().unwrap();
+        assert_eq!(stable.generations(Rules::ADJACENT).count(), 1);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_update() {
+        for &rules in &[Rules::ADJACENT, Rules::LINE_OF_SIGHT] {
+            let plan = EXAMPLES_PART1[0].parse::<SeatingPlan>().unwrap();
+            let size = (plan.width, plan.height);
+            let mut serial = plan.data1.clone();
+            let mut parallel = plan.data1.clone();
+            for src in plan.generations(rules) {
+                let src = src.current();
+                let serial_modified = update_serial(src, &mut serial, size, rules);
+                let parallel_modified = update_parallel(src, &mut parallel, size, rules);
+                assert_eq!(serial_modified, parallel_modified);
+                assert_eq!(serial, parallel);
+            }
+        }
+    }
 }
@@ -42,7 +42,9 @@ impl Direction {
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum Instruction {
     North(i32),
+    South(i32),
     East(i32),
+    West(i32),
     Right(i32),
     Forward(i32),
 }
@@ -59,9 +61,9 @@ impl FromStr for Instruction {
             .map_err(|_| ParseError("could not parse value"))?;
         match opcode {
             'N' => Ok(Instruction::North(value)),
-            'S' => Ok(Instruction::North(-value)),
+            'S' => Ok(Instruction::South(value)),
             'E' => Ok(Instruction::East(value)),
-            'W' => Ok(Instruction::East(-value)),
+            'W' => Ok(Instruction::West(value)),
             'L' if value % 90 == 0 => Ok(Instruction::Right(-value / 90)),
             'R' if value % 90 == 0 => Ok(Instruction::Right(value / 90)),
             'L' | 'R' => Err(ParseError("bad rotation")),
@@ -71,7 +73,21 @@ impl FromStr for Instruction {
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::North(value) => write!(f, "N{}", value),
+            Instruction::South(value) => write!(f, "S{}", value),
+            Instruction::East(value) => write!(f, "E{}", value),
+            Instruction::West(value) => write!(f, "W{}", value),
+            Instruction::Right(steps) if *steps < 0 => write!(f, "L{}", -steps * 90),
+            Instruction::Right(steps) => write!(f, "R{}", steps * 90),
+            Instruction::Forward(value) => write!(f, "F{}", value),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 struct Point {
     north: i32,
     east: i32,
@@ -81,17 +97,42 @@ impl Point {
     pub fn manhattan_distance(&self) -> i32 {
         self.north.abs() + self.east.abs()
     }
+
+    pub fn rotate(&self, quarter_turns: i32) -> Point {
+        // positive quarter turns are clockwise, matching Direction::turn_right
+        match quarter_turns & 0b11 {
+            1 => Point {
+                north: -self.east,
+                east: self.north,
+            },
+            2 => Point {
+                north: -self.north,
+                east: -self.east,
+            },
+            3 => Point {
+                north: self.east,
+                east: -self.north,
+            },
+            _ => *self,
+        }
+    }
 }
 
-fn process_path<'a>(path: impl Iterator<Item = &'a Instruction>) -> i32 {
+fn trace_path<'a>(path: impl Iterator<Item = &'a Instruction>) -> Vec<Point> {
     let mut position = Point { north: 0, east: 0 };
     let mut direction = Direction::East;
+    let mut trace = vec![position];
 
     for instruction in path {
         match instruction {
             Instruction::North(delta) => position.north += *delta,
+            Instruction::South(delta) => position.north -= *delta,
             Instruction::East(delta) => position.east += *delta,
-            Instruction::Right(steps) => direction = direction.turn_right(*steps),
+            Instruction::West(delta) => position.east -= *delta,
+            Instruction::Right(steps) => {
+                direction = direction.turn_right(*steps);
+                continue;
+            }
             Instruction::Forward(steps) => match direction {
                 Direction::East => position.east += *steps,
                 Direction::South => position.north -= *steps,
@@ -99,44 +140,44 @@ fn process_path<'a>(path: impl Iterator<Item = &'a Instruction>) -> i32 {
                 Direction::North => position.north += *steps,
             },
         }
+
+        trace.push(position);
     }
 
-    position.manhattan_distance()
+    trace
 }
 
-fn process_waypoint<'a>(path: impl Iterator<Item = &'a Instruction>) -> i32 {
+fn process_path<'a>(path: impl Iterator<Item = &'a Instruction>) -> i32 {
+    trace_path(path).last().map_or(0, Point::manhattan_distance)
+}
+
+fn trace_waypoint<'a>(path: impl Iterator<Item = &'a Instruction>) -> Vec<Point> {
     let mut ship = Point { north: 0, east: 0 };
     let mut waypoint = Point { north: 1, east: 10 };
+    let mut trace = vec![ship];
 
     for instruction in path {
         match instruction {
             Instruction::North(delta) => waypoint.north += delta,
+            Instruction::South(delta) => waypoint.north -= delta,
             Instruction::East(delta) => waypoint.east += delta,
-            Instruction::Right(steps) => {
-                waypoint = match steps & 0b11 {
-                    1 => Point {
-                        north: -waypoint.east,
-                        east: waypoint.north,
-                    },
-                    2 => Point {
-                        north: -waypoint.north,
-                        east: -waypoint.east,
-                    },
-                    3 => Point {
-                        north: waypoint.east,
-                        east: -waypoint.north,
-                    },
-                    _ => waypoint,
-                }
-            }
+            Instruction::West(delta) => waypoint.east -= delta,
+            Instruction::Right(steps) => waypoint = waypoint.rotate(*steps),
             Instruction::Forward(steps) => {
                 ship.north += waypoint.north * steps;
                 ship.east += waypoint.east * steps;
+                trace.push(ship);
             }
         }
     }
 
-    ship.manhattan_distance()
+    trace
+}
+
+fn process_waypoint<'a>(path: impl Iterator<Item = &'a Instruction>) -> i32 {
+    trace_waypoint(path)
+        .last()
+        .map_or(0, Point::manhattan_distance)
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
@@ -169,7 +210,9 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{process_path, process_waypoint, Direction, Instruction};
+    use super::{
+        process_path, process_waypoint, trace_path, trace_waypoint, Direction, Instruction, Point,
+    };
 
     const EXAMPLE1_TEXT: &str = r"F10
 N3
@@ -228,4 +271,71 @@ F11";
         let result = process_waypoint(EXAMPLE1.iter());
         assert_eq!(result, 286);
     }
+
+    #[test]
+    fn trace_test() {
+        let moves = EXAMPLE1
+            .iter()
+            .filter(|i| !matches!(i, Instruction::Right(_)))
+            .count();
+        let trace = trace_path(EXAMPLE1.iter());
+        assert_eq!(trace.len(), moves + 1);
+        let expected = [(0, 0), (0, 10), (3, 10), (3, 17), (-8, 17)]
+            .iter()
+            .map(|&(north, east)| Point { north, east })
+            .collect::<Vec<_>>();
+        assert_eq!(trace, expected);
+
+        let trace = trace_waypoint(EXAMPLE1.iter());
+        let expected = [(0, 0), (10, 100), (38, 170), (-72, 214)]
+            .iter()
+            .map(|&(north, east)| Point { north, east })
+            .collect::<Vec<_>>();
+        assert_eq!(trace, expected);
+    }
+
+    #[test]
+    fn display_roundtrip_test() {
+        let text = EXAMPLE1_TEXT
+            .lines()
+            .map(|l| l.parse::<Instruction>().unwrap().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(text, EXAMPLE1_TEXT);
+
+        for &line in &["S4", "W12", "L90", "L270", "R180", "E0", "N-3"] {
+            let instruction = line.parse::<Instruction>().unwrap();
+            assert_eq!(instruction.to_string(), line);
+        }
+
+        let path = ["S4", "W12", "F2", "L90", "F3"]
+            .iter()
+            .map(|l| l.parse::<Instruction>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(process_path(path.iter()), 11);
+        assert_eq!(process_waypoint(path.iter()), 17);
+    }
+
+    #[test]
+    fn rotate_test() {
+        const TEST_CASES: [(i32, (i32, i32)); 9] = [
+            (-4, (1, 10)),
+            (-3, (-10, 1)),
+            (-2, (-1, -10)),
+            (-1, (10, -1)),
+            (0, (1, 10)),
+            (1, (-10, 1)),
+            (2, (-1, -10)),
+            (3, (10, -1)),
+            (4, (1, 10)),
+        ];
+
+        let start = Point { north: 1, east: 10 };
+        for &(quarter_turns, (north, east)) in &TEST_CASES {
+            let result = start.rotate(quarter_turns);
+            assert_eq!(result, Point { north, east });
+            assert_eq!(result.rotate(-quarter_turns), start);
+            assert_eq!(result.manhattan_distance(), start.manhattan_distance());
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use std::{error::Error, fmt, fs::read_to_string, path::PathBuf, str::FromStr};
 
+use numtheory::crt;
+
 #[derive(Debug)]
 struct ApplicationError(&'static str);
 
@@ -36,32 +38,11 @@ impl FromStr for Plan {
     }
 }
 
-fn part1(plan: &Plan) -> Option<i64> {
+fn earliest_bus(plan: &Plan) -> Option<(i64, i64)> {
     plan.buses
         .iter()
-        .filter_map(|b| b.map(|bus| (bus, bus - plan.time % bus)))
-        .min_by(|(_, a), (_, b)| a.cmp(b))
-        .map(|(bus, wait)| bus * wait)
-}
-
-fn modular_inverse(a: i64, m: i64) -> Option<i64> {
-    // extended Euclidean algorithm to find inverse a_inv of a modulo m
-    // such that given y = x * a (mod m), x = y * a_inv (mod m)
-    let (mut t_prev, mut t_curr) = (0, 1);
-    let (mut r_prev, mut r_curr) = (m, a);
-    while r_curr != 0 {
-        let quotient = r_prev / r_curr;
-        let t_next = t_prev - quotient * t_curr;
-        let r_next = r_prev - quotient * r_curr;
-        t_prev = std::mem::replace(&mut t_curr, t_next);
-        r_prev = std::mem::replace(&mut r_curr, r_next);
-    }
-
-    if r_prev > 1 {
-        None
-    } else {
-        Some(t_prev)
-    }
+        .filter_map(|b| b.map(|bus| (bus, (-plan.time).rem_euclid(bus))))
+        .min_by_key(|&(_, wait)| wait)
 }
 
 fn part2(buses: &[Option<i64>]) -> Option<i64> {
@@ -71,21 +52,41 @@ fn part2(buses: &[Option<i64>]) -> Option<i64> {
         .filter_map(|(i, b)| b.map(|bus| ((bus - i as i64).rem_euclid(bus), bus)))
         .collect::<Vec<_>>();
 
-    // apply Chinese remainder theorem to equations x ≡ a_i (mod m_i)
-    let m_product: i64 = am.iter().map(|(_, m)| *m).product();
-    let terms = am.iter().map(|&(a, m)| {
-        let n = m_product / m;
-        modular_inverse(n, m).map(|y| a * y * n)
-    });
-    let mut sum = 0;
-    for term in terms {
-        match term {
-            Some(t) => sum += t,
-            None => return None,
+    crt(&am)
+}
+
+#[cfg(test)]
+fn gcd(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        let r = a % b;
+        a = std::mem::replace(&mut b, r);
+    }
+
+    a
+}
+
+#[cfg(test)]
+fn part2_sieve(buses: &[Option<i64>]) -> Option<i64> {
+    // once the timestamp satisfies the buses seen so far, stepping by the LCM of their ids keeps
+    // them satisfied while searching for the next bus
+    let mut time = 0;
+    let mut step = 1;
+    for (i, bus) in buses.iter().enumerate() {
+        if let Some(bus) = *bus {
+            let mut remaining = bus;
+            while (time + i as i64) % bus != 0 {
+                remaining -= 1;
+                if remaining == 0 {
+                    return None;
+                }
+                time += step;
+            }
+
+            step = step.checked_mul(bus / gcd(step, bus))?;
         }
     }
 
-    Some(sum.rem_euclid(m_product))
+    Some(time)
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
@@ -93,8 +94,13 @@ fn run() -> Result<(), Box<dyn Error>> {
         let path = ["data", "day13", "input.txt"].iter().collect::<PathBuf>();
         read_to_string(path)?.parse()?
     };
-    let result1 = part1(&plan).ok_or(ApplicationError("No buses"))?;
-    println!("Part1: result = {}", result1);
+    let (bus, wait) = earliest_bus(&plan).ok_or(ApplicationError("No buses"))?;
+    println!(
+        "Part1: bus = {}, wait = {}, result = {}",
+        bus,
+        wait,
+        bus * wait
+    );
     let result2 =
         part2(&plan.buses).ok_or(ApplicationError("Schedule does not permit solution"))?;
     println!("Part2: result = {}", result2);
@@ -113,7 +119,7 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{part1, part2, Plan};
+    use super::{earliest_bus, part2, part2_sieve, Plan};
 
     const EXAMPLE: &str = r"939
 7,13,x,x,59,x,31,19";
@@ -143,8 +149,21 @@ mod test {
             time: EXAMPLE_TIME,
             buses: EXAMPLE_BUSES.into(),
         };
-        let result = part1(&plan).unwrap();
-        assert_eq!(result, 295);
+        let (bus, wait) = earliest_bus(&plan).unwrap();
+        assert_eq!((bus, wait), (59, 5));
+        assert_eq!(bus * wait, 295);
+
+        let plan = Plan {
+            time: 944,
+            buses: EXAMPLE_BUSES.into(),
+        };
+        assert_eq!(earliest_bus(&plan), Some((59, 0)));
+
+        let plan = Plan {
+            time: EXAMPLE_TIME,
+            buses: vec![None, None],
+        };
+        assert_eq!(earliest_bus(&plan), None);
     }
 
     #[test]
@@ -152,4 +171,58 @@ mod test {
         let result = part2(&EXAMPLE_BUSES).unwrap();
         assert_eq!(result, 1068781);
     }
+
+    #[test]
+    fn test_part2_large() {
+        let target: i64 = 1_234_567_890_123_456_789;
+        let ids = [997, 991, 983, 977, 967, 953, 947, 941, 937];
+        assert!(ids
+            .iter()
+            .try_fold(1i64, |p, &id| p.checked_mul(id))
+            .is_none());
+
+        // place each bus so that it departs its offset after the target time
+        let mut buses = Vec::new();
+        for &id in &ids {
+            let offset = (-target).rem_euclid(id) as usize;
+            if buses.len() <= offset {
+                buses.resize(offset + 1, None);
+            }
+            assert_eq!(buses[offset], None);
+            buses[offset] = Some(id);
+        }
+
+        let result = part2(&buses).unwrap();
+        assert_eq!(result, target);
+        for (offset, bus) in buses.iter().enumerate() {
+            if let Some(id) = bus {
+                assert_eq!((result + offset as i64) % id, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_part2_sieve() {
+        assert_eq!(part2_sieve(&EXAMPLE_BUSES), Some(1068781));
+
+        const EXAMPLES: [(&str, i64); 5] = [
+            ("17,x,13,19", 3417),
+            ("67,7,59,61", 754018),
+            ("67,x,7,59,61", 779210),
+            ("67,7,x,59,61", 1261476),
+            ("1789,37,47,1889", 1202161486),
+        ];
+        for &(schedule, expected) in &EXAMPLES {
+            let buses = schedule
+                .split(',')
+                .map(|t| t.parse().ok())
+                .collect::<Vec<_>>();
+            assert_eq!(part2_sieve(&buses), Some(expected));
+            assert_eq!(part2(&buses), Some(expected));
+        }
+
+        // ids that are not coprime only have a solution if their offsets agree
+        assert_eq!(part2_sieve(&[Some(4), None, Some(6)]), Some(4));
+        assert_eq!(part2_sieve(&[Some(4), Some(6)]), None);
+    }
 }
@@ -1,4 +1,6 @@
-use std::{error::Error, fmt, str::FromStr};
+use std::{error::Error, fmt, iter, str::FromStr};
+
+use ahash::AHashMap;
 
 #[derive(Debug)]
 pub struct ParseError(&'static str);
@@ -17,6 +19,26 @@ pub enum Instruction {
     Assign(u64, u64),
 }
 
+// returns the bits forced to zero, forced to one, and floating
+fn parse_mask(s: &str) -> Result<(u64, u64, u64), ParseError> {
+    let mut zeroes = 0;
+    let mut ones = 0;
+    let mut floating = u64::MAX;
+    for c in s.chars() {
+        zeroes <<= 1;
+        ones <<= 1;
+        floating <<= 1;
+        match c {
+            '0' => zeroes |= 1,
+            '1' => ones |= 1,
+            'X' => floating |= 1,
+            _ => return Err(ParseError("Bad mask character")),
+        }
+    }
+
+    Ok((zeroes, ones, floating))
+}
+
 impl FromStr for Instruction {
     type Err = ParseError;
 
@@ -25,21 +47,7 @@ impl FromStr for Instruction {
         let operation = parts.next().ok_or(ParseError("Missing operation"))?;
         let value_str = parts.next().ok_or(ParseError("Missing value"))?;
         if operation == "mask" {
-            let mut zeroes = 0;
-            let mut ones = 0;
-            let mut floating = u64::MAX;
-            for c in value_str.chars() {
-                zeroes <<= 1;
-                ones <<= 1;
-                floating <<= 1;
-                match c {
-                    '0' => zeroes |= 1,
-                    '1' => ones |= 1,
-                    'X' => floating |= 1,
-                    _ => return Err(ParseError("Bad mask character")),
-                }
-            }
-
+            let (zeroes, ones, floating) = parse_mask(value_str)?;
             Ok(Instruction::Mask(zeroes, ones, floating))
         } else if operation.starts_with("mem[") && operation.ends_with(']') {
             let address = operation[4..operation.len() - 1]
@@ -55,9 +63,73 @@ impl FromStr for Instruction {
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MaskVersion {
+    V1,
+    V2,
+}
+
+const ADDRESS_MASK: u64 = (1 << 36) - 1;
+
+// the mask is given as in the program text, e.g. 000000000000000000000000000000X1001X, and can
+// only be rejected before any addresses are produced, hence the Result
+pub fn floating_addresses(base: u64, mask: &str) -> Result<impl Iterator<Item = u64>, ParseError> {
+    let (_, ones, floating) = parse_mask(mask)?;
+    Ok(masked_addresses(base, ones, floating))
+}
+
+fn masked_addresses(base: u64, ones: u64, floating: u64) -> impl Iterator<Item = u64> {
+    // walk every subset of the floating bits, from all set down to none set
+    let floating_bits = floating & ADDRESS_MASK;
+    let masked_base = (base | ones) & !floating;
+    iter::successors(Some(floating_bits), move |&bits| {
+        if bits == 0 {
+            None
+        } else {
+            Some((bits - 1) & floating_bits)
+        }
+    })
+    .map(move |bits| masked_base | bits)
+}
+
+pub fn run_program<'a>(
+    program: impl Iterator<Item = &'a Instruction>,
+    version: MaskVersion,
+) -> AHashMap<u64, u64> {
+    let mut memory = AHashMap::new();
+    let mut zeroes_mask = 0;
+    let mut ones_mask = 0;
+    let mut floating_mask = 0;
+    for instruction in program {
+        match instruction {
+            Instruction::Mask(zeroes, ones, floating) => {
+                zeroes_mask = *zeroes;
+                ones_mask = *ones;
+                floating_mask = *floating;
+            }
+            Instruction::Assign(address, value) => match version {
+                MaskVersion::V1 => {
+                    memory.insert(*address, (value | ones_mask) & !zeroes_mask);
+                }
+                MaskVersion::V2 => {
+                    for address in masked_addresses(*address, ones_mask, floating_mask) {
+                        memory.insert(address, *value);
+                    }
+                }
+            },
+        }
+    }
+
+    memory
+}
+
 #[cfg(test)]
 mod test {
-    use super::Instruction;
+    use super::{
+        floating_addresses, masked_addresses, run_program, Instruction, MaskVersion, ADDRESS_MASK,
+    };
+
+    use crate::{part1, part2};
 
     const EXAMPLE_TEXT: &str = r"mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
 mem[8] = 11
@@ -79,4 +151,74 @@ mem[8] = 0";
             .collect::<Vec<_>>();
         assert_eq!(program, EXAMPLE_PROGRAM);
     }
+
+    const EXAMPLE_PROGRAM2: [Instruction; 4] = [
+        Instruction::Mask(!0b110011, 0b10010, 0b100001),
+        Instruction::Assign(42, 100),
+        Instruction::Mask(!0b1011, 0, 0b1011),
+        Instruction::Assign(26, 1),
+    ];
+
+    #[test]
+    fn execute_test() {
+        let result = run_program(EXAMPLE_PROGRAM.iter(), MaskVersion::V1)
+            .values()
+            .sum::<u64>();
+        assert_eq!(result, 165);
+        assert_eq!(result, part1::execute_program(EXAMPLE_PROGRAM.iter()));
+
+        let result = run_program(EXAMPLE_PROGRAM2.iter(), MaskVersion::V2)
+            .values()
+            .sum::<u64>();
+        assert_eq!(result, 208);
+        assert_eq!(result, part2::execute_program(EXAMPLE_PROGRAM2.iter()));
+
+        // 100 masked by X1001X is 50, and 1 masked by X0XX is unchanged
+        let memory = run_program(EXAMPLE_PROGRAM2.iter(), MaskVersion::V1);
+        assert_eq!(memory.values().sum::<u64>(), 51);
+    }
+
+    #[test]
+    fn memory_test() {
+        let memory = run_program(EXAMPLE_PROGRAM.iter(), MaskVersion::V1);
+        let mut entries = memory.into_iter().collect::<Vec<_>>();
+        entries.sort_unstable();
+        assert_eq!(entries, vec![(7, 101), (8, 64)]);
+
+        let memory = run_program(EXAMPLE_PROGRAM2.iter(), MaskVersion::V2);
+        let mut entries = memory.into_iter().collect::<Vec<_>>();
+        entries.sort_unstable();
+        let expected = vec![
+            (16, 1),
+            (17, 1),
+            (18, 1),
+            (19, 1),
+            (24, 1),
+            (25, 1),
+            (26, 1),
+            (27, 1),
+            (58, 100),
+            (59, 100),
+        ];
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn floating_addresses_test() {
+        let mut addresses = floating_addresses(42, "000000000000000000000000000000X1001X")
+            .unwrap()
+            .collect::<Vec<_>>();
+        addresses.sort_unstable();
+        assert_eq!(addresses, vec![26, 27, 58, 59]);
+
+        let mut addresses = floating_addresses(26, "00000000000000000000000000000000X0XX")
+            .unwrap()
+            .collect::<Vec<_>>();
+        addresses.sort_unstable();
+        assert_eq!(addresses, vec![16, 17, 18, 19, 24, 25, 26, 27]);
+        assert!(floating_addresses(26, "0000X0Y").is_err());
+
+        assert_eq!(masked_addresses(5, 2, 0).collect::<Vec<_>>(), vec![7]);
+        assert_eq!(masked_addresses(0, 0, ADDRESS_MASK).take(3).count(), 3);
+    }
 }
@@ -1,5 +1,6 @@
 use std::{
     error::Error,
+    fmt,
     fs::File,
     io::{BufRead, BufReader},
     path::PathBuf,
@@ -9,7 +10,45 @@ mod common;
 mod part1;
 mod part2;
 
+#[derive(Debug)]
+struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid arguments: {}", self.0)
+    }
+}
+
+impl Error for ArgsError {}
+
+// --addresses BASE MASK lists the addresses a version 2 mask writes to for the given base address
+fn show_addresses(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let base = args
+        .next()
+        .ok_or_else(|| ArgsError("missing base address".to_string()))?;
+    let base = base
+        .parse()
+        .map_err(|_| ArgsError(format!("invalid base address {:?}", base)))?;
+    let mask = args
+        .next()
+        .ok_or_else(|| ArgsError("missing mask".to_string()))?;
+    let mut addresses = common::floating_addresses(base, &mask)?.collect::<Vec<_>>();
+    addresses.sort_unstable();
+    for address in addresses {
+        println!("{}", address);
+    }
+
+    Ok(())
+}
+
 fn run() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("--addresses") => return show_addresses(args),
+        Some(arg) => return Err(ArgsError(format!("unknown argument {:?}", arg)).into()),
+        None => (),
+    }
+
     let program = {
         let path = ["data", "day14", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
@@ -1,27 +1,7 @@
-use ahash::AHashMap;
-
-use crate::common::Instruction;
+use crate::common::{run_program, Instruction, MaskVersion};
 
 pub fn execute_program<'a>(program: impl Iterator<Item = &'a Instruction>) -> u64 {
-    let mut memory = AHashMap::new();
-    let mut or_mask = 0;
-    let mut and_mask = u64::MAX;
-    for instruction in program {
-        match instruction {
-            Instruction::Mask(zeroes, ones, _) => {
-                or_mask = *ones;
-                and_mask = !zeroes;
-            }
-            Instruction::Assign(address, value) => {
-                match (value | or_mask) & and_mask {
-                    0 => memory.remove(address),
-                    v => memory.insert(*address, v),
-                };
-            }
-        }
-    }
-
-    memory.values().sum()
+    run_program(program, MaskVersion::V1).values().sum()
 }
 
 #[cfg(test)]
@@ -1,24 +1,56 @@
+use std::{error::Error, fmt};
+
 use ahash::AHashMap;
 
+#[derive(Debug)]
+struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid arguments: {}", self.0)
+    }
+}
+
+impl Error for ArgsError {}
+
+fn sequence(initial: &[usize]) -> impl Iterator<Item = usize> + '_ {
+    let mut memory = AHashMap::new();
+    let mut next = 0;
+    (0..).map(move |pos| {
+        let item = initial.get(pos).copied().unwrap_or(next);
+        next = memory
+            .insert(item, pos)
+            .map_or(0, |prev_pos| pos - prev_pos);
+        item
+    })
+}
+
 fn elf_sequence(initial: &[usize], n: usize) -> usize {
     assert!(!initial.is_empty());
-    let mut memory = initial
-        .iter()
-        .enumerate()
-        .map(|(i, x)| (*x, i + 1))
-        .collect::<AHashMap<_, _>>();
-    let mut item = *initial.last().unwrap();
+    sequence(initial).nth(n - 1).unwrap()
+}
+
+fn elf_sequence_vec(initial: &[usize], n: usize) -> usize {
+    assert!(!initial.is_empty());
+    assert!(n <= u32::MAX as usize);
+    if n <= initial.len() {
+        return initial[n - 1];
+    }
+
+    // every number spoken after the initial ones is a difference of turns, so is less than n
+    let size = initial.iter().map(|x| x + 1).max().unwrap().max(n);
+    let mut memory = vec![0u32; size];
+    let (&last, rest) = initial.split_last().unwrap();
+    for (i, x) in rest.iter().enumerate() {
+        memory[*x] = (i + 1) as u32;
+    }
+
+    let mut item = last;
     for pos in initial.len()..n {
-        let mut next_item = 0;
-        memory
-            .entry(item)
-            .and_modify(|prev_pos| {
-                next_item = pos - *prev_pos;
-                *prev_pos = pos;
-            })
-            .or_insert(pos);
-
-        item = next_item;
+        item = match std::mem::replace(&mut memory[item], pos as u32) {
+            0 => 0,
+            prev_pos => pos - prev_pos as usize,
+        };
     }
 
     item
@@ -26,14 +58,90 @@ fn elf_sequence(initial: &[usize], n: usize) -> usize {
 
 const INPUT: [usize; 6] = [1, 0, 16, 5, 17, 4];
 
+#[derive(Debug, PartialEq)]
+struct Options {
+    initial: Vec<usize>,
+    count: Option<usize>,
+    prefix: Option<usize>,
+}
+
+fn parse_count(arg: &str, name: &str) -> Result<usize, ArgsError> {
+    arg.parse()
+        .ok()
+        .filter(|&n| n > 0 && n <= u32::MAX as usize)
+        .ok_or_else(|| ArgsError(format!("invalid {} {:?}", name, arg)))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, ArgsError> {
+    let mut options = Options {
+        initial: INPUT.to_vec(),
+        count: None,
+        prefix: None,
+    };
+
+    // the starting numbers and turn count are positional, in that order
+    let mut positional = 0;
+    while let Some(arg) = args.next() {
+        if arg == "--prefix" {
+            let value = args
+                .next()
+                .ok_or_else(|| ArgsError("missing prefix length".to_string()))?;
+            options.prefix = Some(parse_count(&value, "prefix length")?);
+            continue;
+        }
+
+        match positional {
+            0 => {
+                options.initial = arg
+                    .split(',')
+                    .map(|x| x.trim().parse().ok())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| ArgsError(format!("invalid starting numbers {:?}", arg)))?
+            }
+            1 => options.count = Some(parse_count(&arg, "count")?),
+            _ => return Err(ArgsError(format!("unexpected argument {:?}", arg))),
+        }
+        positional += 1;
+    }
+
+    Ok(options)
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let options = parse_args(std::env::args().skip(1))?;
+    let initial = &options.initial;
+    if let Some(length) = options.prefix {
+        let prefix = sequence(initial)
+            .take(length)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>();
+        println!("First {} numbers: {}", length, prefix.join(","));
+    }
+
+    match options.count {
+        Some(n) => println!("Turn {}: result = {}", n, elf_sequence_vec(initial, n)),
+        None => {
+            println!("Part 1: result = {}", elf_sequence(initial, 2020));
+            println!("Part 2: result = {}", elf_sequence_vec(initial, 30000000));
+        }
+    }
+
+    Ok(())
+}
+
 fn main() {
-    println!("Part 1: result = {}", elf_sequence(&INPUT, 2020));
-    println!("Part 2: result = {}", elf_sequence(&INPUT, 30000000));
+    std::process::exit(match run() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error occurred: {}", e);
+            1
+        }
+    });
 }
 
 #[cfg(test)]
 mod test {
-    use super::elf_sequence;
+    use super::{elf_sequence, elf_sequence_vec, parse_args, sequence, Options, INPUT};
 
     #[test]
     fn part1_test() {
@@ -50,9 +158,84 @@ mod test {
         for (sequence, expected) in &TESTS {
             let result = elf_sequence(sequence, 2020);
             assert_eq!(result, *expected);
+            let result = elf_sequence_vec(sequence, 2020);
+            assert_eq!(result, *expected);
         }
     }
 
+    #[test]
+    fn sequence_test() {
+        let result = sequence(&[0, 3, 6]).take(10).collect::<Vec<_>>();
+        assert_eq!(result, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+
+        let result = sequence(&[1, 1]).take(6).collect::<Vec<_>>();
+        assert_eq!(result, vec![1, 1, 1, 1, 1, 1]);
+        let result = sequence(&[2, 0, 2]).take(6).collect::<Vec<_>>();
+        assert_eq!(result, vec![2, 0, 2, 2, 1, 0]);
+        assert_eq!(elf_sequence(&[0, 3, 6], 2), 3);
+    }
+
+    #[test]
+    fn parse_args_test() {
+        let args = |a: &[&str]| {
+            a.iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+        let options = |initial: &[usize], count, prefix| Options {
+            initial: initial.to_vec(),
+            count,
+            prefix,
+        };
+        assert_eq!(parse_args(args(&[])).unwrap(), options(&INPUT, None, None));
+        assert_eq!(
+            parse_args(args(&["0,3,6"])).unwrap(),
+            options(&[0, 3, 6], None, None)
+        );
+        assert_eq!(
+            parse_args(args(&["3, 1,2", "2020"])).unwrap(),
+            options(&[3, 1, 2], Some(2020), None)
+        );
+        assert_eq!(
+            parse_args(args(&["--prefix", "10", "0,3,6"])).unwrap(),
+            options(&[0, 3, 6], None, Some(10))
+        );
+        assert_eq!(
+            parse_args(args(&["0,3,6", "2020", "--prefix", "5"])).unwrap(),
+            options(&[0, 3, 6], Some(2020), Some(5))
+        );
+
+        let error = parse_args(args(&["0,x,6"])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid arguments: invalid starting numbers \"0,x,6\""
+        );
+        assert!(parse_args(args(&[""])).is_err());
+        assert!(parse_args(args(&["0,3,6", "0"])).is_err());
+        assert!(parse_args(args(&["0,3,6", "-1"])).is_err());
+        assert!(parse_args(args(&["0,3,6", "2020", "1"])).is_err());
+        assert!(parse_args(args(&["--prefix"])).is_err());
+        assert!(parse_args(args(&["--prefix", "0"])).is_err());
+    }
+
+    #[test]
+    fn elf_sequence_vec_test() {
+        for initial in &[
+            vec![0, 3, 6],
+            vec![1, 0, 16, 5, 17, 4],
+            vec![2020, 7],
+            vec![5],
+        ] {
+            for n in 1..300 {
+                assert_eq!(elf_sequence_vec(initial, n), elf_sequence(initial, n));
+            }
+        }
+
+        assert_eq!(elf_sequence_vec(&[0, 3, 6], 1), 0);
+        assert_eq!(elf_sequence_vec(&[0, 3, 6], 3), 6);
+    }
+
     // To save time, run each part 2 test as its own test case (enabling
     // cargo test to run them in parallel), and only on optimized builds.
     macro_rules! part2_test {
@@ -62,6 +245,8 @@ mod test {
             fn $name() {
                 let result = elf_sequence(&$seq, 30000000);
                 assert_eq!(result, $expected);
+                let result = elf_sequence_vec(&$seq, 30000000);
+                assert_eq!(result, $expected);
             }
         };
     }
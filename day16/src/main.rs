@@ -16,19 +16,17 @@ fn run() -> Result<(), Box<dyn Error>> {
     let problem = {
         let path = ["data", "day16", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
-        Problem::parse(BufReader::new(file).lines().filter_map(Result::ok))?
+        Problem::parse(BufReader::new(file).lines().map_while(Result::ok))?
     };
 
     println!("Part 1: rate = {}", problem.error_rate());
 
-    let field_assignments = problem.assign_fields();
     let ticket = problem.your_ticket();
     let result = problem
-        .fields()
+        .field_columns()
         .iter()
-        .enumerate()
-        .filter(|(_, f)| f.name().starts_with("departure"))
-        .map(|(i, _)| ticket[field_assignments[i]] as u64)
+        .filter(|(name, _)| name.starts_with("departure"))
+        .map(|(_, &col)| ticket[col] as u64)
         .product::<u64>();
 
     println!("Part 2: result = {}", result);
@@ -1,5 +1,6 @@
 use std::{error::Error, fmt, iter, ops::RangeInclusive, str::FromStr};
 
+use ahash::AHashMap;
 use bitvec::prelude::*;
 use regex::Regex;
 
@@ -158,10 +159,16 @@ impl Problem {
             .chain(iter::once(self.your_ticket.as_slice()))
     }
 
+    pub fn invalid_tickets(&self) -> Vec<&[usize]> {
+        self.other_tickets()
+            .filter(|t| t.iter().any(|&v| !self.allowed[v]))
+            .collect()
+    }
+
     pub fn error_rate(&self) -> u64 {
-        self.ticket_data
+        self.invalid_tickets()
             .iter()
-            .copied()
+            .flat_map(|t| t.iter().copied())
             .filter(|&v| !self.allowed[v])
             .map(|v| v as u64)
             .sum()
@@ -180,19 +187,58 @@ impl Problem {
                     .for_each(|(_, a)| a.set(col, false));
             });
 
-        let mut field_assignments = vec![usize::MAX; self.fields.len()];
-        for _ in 0..self.fields.len() {
-            let (field, allowed) = allowed_columns
-                .iter()
-                .enumerate()
-                .find(|(_, a)| a.count_ones() == 1)
-                .expect("Backtracking not implemented");
-            let col = allowed.iter().enumerate().find(|(_, b)| **b).unwrap().0;
-            field_assignments[field] = col;
-            allowed_columns.iter_mut().for_each(|a| a.set(col, false));
-        }
+        let field_assignments = vec![usize::MAX; self.fields.len()];
+        assign_columns(allowed_columns, field_assignments).expect("No valid field assignment")
+    }
+
+    pub fn field_columns(&self) -> AHashMap<String, usize> {
+        self.fields()
+            .iter()
+            .map(|f| f.name().to_owned())
+            .zip(self.assign_fields())
+            .collect()
+    }
+}
 
-        field_assignments
+fn assign_columns(
+    mut allowed_columns: Vec<BitVec>,
+    mut field_assignments: Vec<usize>,
+) -> Option<Vec<usize>> {
+    loop {
+        // take the unassigned field with the fewest candidate columns
+        let (field, allowed) = match allowed_columns
+            .iter()
+            .enumerate()
+            .filter(|(f, _)| field_assignments[*f] == usize::MAX)
+            .min_by_key(|(_, a)| a.count_ones())
+        {
+            Some(candidate) => candidate,
+            None => return Some(field_assignments),
+        };
+
+        let columns = allowed
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b)
+            .map(|(col, _)| col)
+            .collect::<Vec<_>>();
+        match columns[..] {
+            [] => return None,
+            [col] => {
+                field_assignments[field] = col;
+                allowed_columns.iter_mut().for_each(|a| a.set(col, false));
+            }
+            _ => {
+                // no field is forced, so guess each column in turn and backtrack on failure
+                return columns.into_iter().find_map(|col| {
+                    let mut allowed_columns = allowed_columns.clone();
+                    let mut field_assignments = field_assignments.clone();
+                    field_assignments[field] = col;
+                    allowed_columns.iter_mut().for_each(|a| a.set(col, false));
+                    assign_columns(allowed_columns, field_assignments)
+                });
+            }
+        }
     }
 }
 
@@ -250,6 +296,17 @@ nearby tickets:
         assert_eq!(result, 71);
     }
 
+    #[test]
+    fn invalid_tickets_test() {
+        let problem = Problem::parse(EXAMPLE.lines()).unwrap();
+        let result = problem.invalid_tickets();
+        assert_eq!(result, vec![&[40, 4, 50], &[55, 2, 20], &[38, 6, 12]]);
+
+        let problem = Problem::parse(EXAMPLE2.lines()).unwrap();
+        assert!(problem.invalid_tickets().is_empty());
+        assert_eq!(problem.error_rate(), 0);
+    }
+
     const EXAMPLE2: &str = r"class: 0-1 or 4-19
 row: 0-5 or 8-19
 seat: 0-13 or 16-19
@@ -270,4 +327,43 @@ nearby tickets:
         let field_assignments = problem.assign_fields();
         assert_eq!(&EXPECTED_ASSIGNMENTS[..], field_assignments);
     }
+
+    #[test]
+    fn field_columns_test() {
+        let problem = Problem::parse(EXAMPLE2.lines()).unwrap();
+        let mut field_columns = problem.field_columns().into_iter().collect::<Vec<_>>();
+        field_columns.sort_unstable();
+        let expected = [("class", 1), ("row", 0), ("seat", 2)]
+            .iter()
+            .map(|&(name, col)| (name.to_owned(), col))
+            .collect::<Vec<_>>();
+        assert_eq!(field_columns, expected);
+    }
+
+    const EXAMPLE_BACKTRACK: &str = r"a: 1-1 or 3-3
+b: 1-2 or 10-10
+c: 1-2 or 10-10
+
+your ticket:
+1,2,3
+
+nearby tickets:
+1,2,3";
+
+    #[test]
+    fn backtrack_test() {
+        // every field has two candidate columns, and guessing column 0 for field a leaves no
+        // column for c, so the search has to backtrack; a uniquely solvable puzzle always has a
+        // forced field, so b and c may take columns 0 and 1 either way round
+        let problem = Problem::parse(EXAMPLE_BACKTRACK.lines()).unwrap();
+        let field_assignments = problem.assign_fields();
+
+        let mut columns = field_assignments.clone();
+        columns.sort_unstable();
+        assert_eq!(columns, vec![0, 1, 2]);
+        assert_eq!(field_assignments[0], 2);
+        for (field, &col) in problem.fields().iter().zip(field_assignments.iter()) {
+            assert!(problem.all_tickets().all(|t| field.contains(&t[col])));
+        }
+    }
 }
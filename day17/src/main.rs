@@ -1,24 +1,90 @@
-use std::{error::Error, fs::read_to_string, path::PathBuf};
+use std::{error::Error, fmt, fs::read_to_string, path::PathBuf};
 
+mod parse;
 mod simulation;
-use simulation::{ParseSimulationError, Simulation};
+mod sparse;
+use parse::ParseSimulationError;
+use simulation::Simulation;
+use sparse::SparseSimulation;
 
-fn process(initial: &str, dimensions: usize) -> Result<usize, ParseSimulationError> {
-    let mut simulation = Simulation::parse(initial, dimensions)?;
-    for _ in 0..6 {
-        simulation.update();
+#[derive(Debug)]
+struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid arguments: {}", self.0)
     }
+}
+
+impl Error for ArgsError {}
+
+const DEFAULT_STEPS: usize = 6;
+
+fn process(
+    initial: &str,
+    dimensions: usize,
+    steps: usize,
+    dense: bool,
+) -> Result<(usize, Vec<usize>), ParseSimulationError> {
+    // returns the final number of active cubes and the number after each step; the sparse
+    // simulation is the default, the original dense grid is kept for comparison
+    if dense {
+        let mut simulation = Simulation::parse(initial, dimensions)?;
+        let history = simulation.run_history(steps);
+        Ok((simulation.active_count(), history))
+    } else {
+        let mut simulation = SparseSimulation::parse(initial, dimensions)?;
+        let history = simulation.run_history(steps);
+        Ok((simulation.active_count(), history))
+    }
+}
 
-    Ok(simulation.active_count())
+fn format_history(history: &[usize]) -> String {
+    history
+        .iter()
+        .map(|count| count.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// returns the number of steps and whether to use the dense simulation
+fn parse_args(args: impl Iterator<Item = String>) -> Result<(usize, bool), ArgsError> {
+    let mut steps = None;
+    let mut dense = false;
+    for arg in args {
+        match arg.as_str() {
+            "--dense" => dense = true,
+            _ if steps.is_none() => {
+                let value = arg
+                    .parse()
+                    .map_err(|_| ArgsError(format!("invalid number of steps {:?}", arg)))?;
+                steps = Some(value);
+            }
+            _ => return Err(ArgsError(format!("unexpected argument {:?}", arg))),
+        }
+    }
+
+    Ok((steps.unwrap_or(DEFAULT_STEPS), dense))
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
+    let (steps, dense) = parse_args(std::env::args().skip(1))?;
+
     let initial = {
         let path = ["data", "day17", "input.txt"].iter().collect::<PathBuf>();
         read_to_string(path)?
     };
-    println!("Part 1: result = {}", process(&initial, 3)?);
-    println!("Part 2: result = {}", process(&initial, 4)?);
+    for (part, dimensions) in (1..).zip(3..=4) {
+        let (result, history) = process(&initial, dimensions, steps, dense)?;
+        println!("Part {}: result = {}", part, result);
+        if !history.is_empty() {
+            println!(
+                "Part {}: active cubes after each step = {}",
+                part,
+                format_history(&history)
+            );
+        }
+    }
     Ok(())
 }
 
@@ -31,3 +97,45 @@ fn main() {
         }
     });
 }
+
+#[cfg(test)]
+mod test {
+    use super::{format_history, parse_args, process, DEFAULT_STEPS};
+
+    const EXAMPLE: &str = r".#.
+..#
+###";
+
+    #[test]
+    fn process_steps() {
+        for &dense in &[false, true] {
+            assert_eq!(process(EXAMPLE, 3, 0, dense).unwrap(), (5, vec![]));
+            assert_eq!(process(EXAMPLE, 3, 1, dense).unwrap(), (11, vec![11]));
+            let (result, history) = process(EXAMPLE, 3, DEFAULT_STEPS, dense).unwrap();
+            assert_eq!(result, 112);
+            assert_eq!(history[..3], [11, 21, 38]);
+            assert_eq!(process(EXAMPLE, 4, 1, dense).unwrap(), (29, vec![29]));
+        }
+    }
+
+    #[test]
+    fn parse_args_test() {
+        let args = |a: &[&str]| {
+            a.iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+        assert_eq!(parse_args(args(&[])).unwrap(), (DEFAULT_STEPS, false));
+        assert_eq!(parse_args(args(&["3"])).unwrap(), (3, false));
+        assert_eq!(parse_args(args(&["--dense", "2"])).unwrap(), (2, true));
+        assert!(parse_args(args(&["x"])).is_err());
+        assert!(parse_args(args(&["1", "2"])).is_err());
+    }
+
+    #[test]
+    fn format_history_test() {
+        assert_eq!(format_history(&[11, 21, 38]), "11, 21, 38");
+        assert_eq!(format_history(&[]), "");
+    }
+}
@@ -0,0 +1,47 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug)]
+pub struct ParseSimulationError(&'static str);
+
+impl fmt::Display for ParseSimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Simulation parse error: {}", self.0)
+    }
+}
+
+impl Error for ParseSimulationError {}
+
+// returns the width and height of the initial slice, and whether each cell is active in row order
+pub fn parse_initial(
+    s: &str,
+    dimensions: usize,
+) -> Result<(usize, usize, Vec<bool>), ParseSimulationError> {
+    if dimensions < 2 {
+        return Err(ParseSimulationError("Needs at least two dimensions"));
+    }
+
+    let lines = s.lines().collect::<Vec<_>>();
+    if lines.is_empty() {
+        return Err(ParseSimulationError("Empty grid"));
+    }
+
+    if lines[0].is_empty() {
+        return Err(ParseSimulationError("No row data"));
+    }
+    let width = lines[0].len();
+    if lines.iter().any(|line| line.len() != width) {
+        return Err(ParseSimulationError("Inconsistent widths"));
+    }
+
+    let cells = lines
+        .iter()
+        .flat_map(|line| line.chars())
+        .map(|c| match c {
+            '.' => Ok(false),
+            '#' => Ok(true),
+            _ => Err(ParseSimulationError("Unexpected character")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((width, lines.len(), cells))
+}
@@ -1,17 +1,8 @@
-use std::{cmp::min, error::Error, fmt};
+use std::cmp::min;
 
 use itertools::izip;
 
-#[derive(Debug)]
-pub struct ParseSimulationError(&'static str);
-
-impl fmt::Display for ParseSimulationError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Simulation parse error: {}", self.0)
-    }
-}
-
-impl Error for ParseSimulationError {}
+use crate::parse::{parse_initial, ParseSimulationError};
 
 #[derive(Debug, Clone, Copy)]
 enum Cube {
@@ -72,35 +63,14 @@ pub struct Simulation {
 
 impl Simulation {
     pub fn parse(s: &str, dimensions: usize) -> Result<Self, ParseSimulationError> {
-        if dimensions < 2 {
-            return Err(ParseSimulationError("Needs at least two dimensions"));
-        }
-
+        let (width, height, cells) = parse_initial(s, dimensions)?;
         let mut axes = vec![1; dimensions];
-
-        let lines = s.lines().collect::<Vec<_>>();
-        if lines.is_empty() {
-            return Err(ParseSimulationError("Empty grid"));
-        }
-        axes[1] = lines.len();
-
-        if lines[0].is_empty() {
-            return Err(ParseSimulationError("No row data"));
-        }
-        axes[0] = lines[0].len();
-        if lines.iter().any(|line| line.len() != axes[0]) {
-            return Err(ParseSimulationError("Inconsistent widths"));
-        }
-
-        let data = lines
-            .iter()
-            .flat_map(|line| line.chars())
-            .map(|c| match c {
-                '.' => Ok(Cube::Inactive),
-                '#' => Ok(Cube::Active),
-                _ => Err(ParseSimulationError("Unexpected character")),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        axes[0] = width;
+        axes[1] = height;
+        let data = cells
+            .into_iter()
+            .map(|active| if active { Cube::Active } else { Cube::Inactive })
+            .collect();
 
         Ok(Self { data, axes })
     }
@@ -137,7 +107,7 @@ impl Simulation {
         assert_eq!(src_pos.len(), end.len());
         assert_eq!(src_pos.len(), scratch_pos.len());
 
-        scratch_pos.copy_from_slice(&start);
+        scratch_pos.copy_from_slice(start);
 
         let mut current_cube = Cube::Inactive;
         let mut active_count = 0;
@@ -184,6 +154,15 @@ impl Simulation {
         self.axes = new_axes;
         self.data = new_data;
     }
+
+    pub fn run_history(&mut self, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                self.update();
+                self.active_count()
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -218,4 +197,11 @@ mod test {
         }
         assert_eq!(simulation.active_count(), 848);
     }
+
+    #[test]
+    fn history_3d() {
+        let mut simulation = Simulation::parse(EXAMPLE, 3).unwrap();
+        assert_eq!(simulation.run_history(3), [11, 21, 38]);
+        assert!(simulation.run_history(0).is_empty());
+    }
 }
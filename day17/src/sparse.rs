@@ -0,0 +1,125 @@
+use ahash::{AHashMap, AHashSet};
+use itertools::Itertools;
+
+use crate::parse::{parse_initial, ParseSimulationError};
+
+#[derive(Debug)]
+pub struct SparseSimulation {
+    active: AHashSet<Vec<i32>>,
+    offsets: Vec<Vec<i32>>,
+}
+
+impl SparseSimulation {
+    pub fn parse(s: &str, dimensions: usize) -> Result<Self, ParseSimulationError> {
+        let (width, _, cells) = parse_initial(s, dimensions)?;
+        let active = cells
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, active)| active)
+            .map(|(i, _)| {
+                let mut coords = vec![0; dimensions];
+                coords[0] = (i % width) as i32;
+                coords[1] = (i / width) as i32;
+                coords
+            })
+            .collect();
+
+        let offsets = (0..dimensions)
+            .map(|_| -1..=1)
+            .multi_cartesian_product()
+            .filter(|offset| offset.iter().any(|&d| d != 0))
+            .collect();
+
+        Ok(Self { active, offsets })
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn update(&mut self) {
+        // only cells next to an active cell can have any active neighbors
+        let mut neighbor_counts = AHashMap::new();
+        for coords in &self.active {
+            for offset in &self.offsets {
+                let neighbor = coords
+                    .iter()
+                    .zip(offset)
+                    .map(|(c, d)| c + d)
+                    .collect::<Vec<_>>();
+                *neighbor_counts.entry(neighbor).or_insert(0) += 1;
+            }
+        }
+
+        self.active = neighbor_counts
+            .into_iter()
+            .filter(|(coords, count)| *count == 3 || (*count == 2 && self.active.contains(coords)))
+            .map(|(coords, _)| coords)
+            .collect();
+    }
+
+    pub fn run_history(&mut self, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                self.update();
+                self.active_count()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SparseSimulation;
+
+    use crate::simulation::Simulation;
+
+    const EXAMPLE: &str = r".#.
+..#
+###";
+
+    #[test]
+    fn dense_parity() {
+        for dimensions in 3..=4 {
+            let mut sparse = SparseSimulation::parse(EXAMPLE, dimensions).unwrap();
+            let mut dense = Simulation::parse(EXAMPLE, dimensions).unwrap();
+            assert_eq!(sparse.active_count(), dense.active_count());
+            for _ in 0..3 {
+                sparse.update();
+                dense.update();
+                assert_eq!(sparse.active_count(), dense.active_count());
+            }
+        }
+    }
+
+    #[test]
+    fn six_steps() {
+        for &(dimensions, expected) in &[(3, 112), (4, 848)] {
+            let mut simulation = SparseSimulation::parse(EXAMPLE, dimensions).unwrap();
+            for _ in 0..6 {
+                simulation.update();
+            }
+            assert_eq!(simulation.active_count(), expected);
+        }
+    }
+
+    #[test]
+    fn history_3d() {
+        let mut simulation = SparseSimulation::parse(EXAMPLE, 3).unwrap();
+        let history = simulation.run_history(6);
+        assert_eq!(history.len(), 6);
+        assert_eq!(history[0], 11);
+        assert_eq!(history[..3], [11, 21, 38]);
+        assert_eq!(history[5], 112);
+        assert_eq!(simulation.active_count(), 112);
+        assert!(simulation.run_history(0).is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(SparseSimulation::parse(EXAMPLE, 1).is_err());
+        assert!(SparseSimulation::parse("", 3).is_err());
+        assert!(SparseSimulation::parse(".#\n#", 3).is_err());
+        assert!(SparseSimulation::parse(".#\n#x", 3).is_err());
+    }
+}
@@ -7,12 +7,13 @@ use std::{
 
 mod parser;
 
-use parser::{parse, ParseError};
+use parser::{parse, ParseError, ADDITION_PRECEDENCE, FLAT_PRECEDENCE};
 
 fn part1(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<(), ParseError> {
+    let precedence = FLAT_PRECEDENCE.iter().copied().collect();
     let mut result = 0;
     for line in lines {
-        result += parse(line.as_ref(), false)?;
+        result += parse(line.as_ref(), &precedence)?;
     }
 
     println!("Part 1: result = {}", result);
@@ -20,9 +21,10 @@ fn part1(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<(), ParseError>
 }
 
 fn part2(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<(), ParseError> {
+    let precedence = ADDITION_PRECEDENCE.iter().copied().collect();
     let mut result = 0;
     for line in lines {
-        result += parse(line.as_ref(), true)?;
+        result += parse(line.as_ref(), &precedence)?;
     }
 
     println!("Part 2: result = {}", result);
@@ -1,116 +1,146 @@
-use std::{error::Error, fmt};
+use std::{collections::HashMap, error, fmt};
 
 use nom::{
     branch::alt,
-    character::complete::{char, digit1, multispace0, one_of},
-    combinator::{all_consuming, map, map_res},
-    multi::fold_many0,
-    sequence::{delimited, preceded, tuple},
+    character::complete::{anychar, char, digit1, multispace0},
+    combinator::{all_consuming, map, map_opt, map_res},
+    sequence::{delimited, preceded},
     Finish, IResult,
 };
 
 #[derive(Debug)]
-pub struct ParseError(String);
+pub struct ParseError {
+    message: &'static str,
+    offset: Option<usize>,
+}
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Parse error: {}", self.0)
+        match self.offset {
+            Some(offset) => write!(f, "Parse error: {} at column {}", self.message, offset + 1),
+            None => write!(f, "Parse error: {}", self.message),
+        }
     }
 }
 
-impl Error for ParseError {}
+impl error::Error for ParseError {}
 
-trait Parser {
-    fn expr(s: &str) -> IResult<&str, u64>;
-}
+pub const FLAT_PRECEDENCE: [(char, u8); 4] = [('+', 1), ('-', 1), ('*', 1), ('/', 1)];
+pub const ADDITION_PRECEDENCE: [(char, u8); 4] = [('+', 2), ('-', 2), ('*', 1), ('/', 1)];
 
-#[derive(Debug)]
-enum Operator {
+#[derive(Debug, Eq, PartialEq)]
+pub enum Operator {
     Add,
+    Subtract,
     Multiply,
+    Divide,
 }
 
-fn number(s: &str) -> IResult<&str, u64> {
-    map_res(digit1, str::parse)(s)
+impl Operator {
+    fn from_char(c: char) -> Option<Operator> {
+        match c {
+            '+' => Some(Operator::Add),
+            '-' => Some(Operator::Subtract),
+            '*' => Some(Operator::Multiply),
+            '/' => Some(Operator::Divide),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        match self {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Subtract => lhs.checked_sub(rhs),
+            Operator::Multiply => lhs.checked_mul(rhs),
+            Operator::Divide => lhs.checked_div(rhs),
+        }
+    }
 }
 
-fn operator(s: &str) -> IResult<&str, Operator> {
-    map(one_of("+*"), |c| match c {
-        '+' => Operator::Add,
-        '*' => Operator::Multiply,
-        _ => unreachable!(),
-    })(s)
+#[derive(Debug, Eq, PartialEq)]
+pub enum Expr {
+    Num(u64),
+    BinOp(Box<Expr>, Operator, Box<Expr>),
 }
 
-fn bracket_expr<P: Parser>(s: &str) -> IResult<&str, u64> {
-    delimited(char('('), P::expr, char(')'))(s)
+pub fn eval(expr: &Expr) -> Option<u64> {
+    match expr {
+        Expr::Num(value) => Some(*value),
+        Expr::BinOp(lhs, op, rhs) => op.apply(eval(lhs)?, eval(rhs)?),
+    }
 }
 
-fn unary_expr<P: Parser>(s: &str) -> IResult<&str, u64> {
-    alt((number, bracket_expr::<P>))(s)
+fn number(s: &str) -> IResult<&str, u64> {
+    map_res(digit1, str::parse)(s)
 }
 
-struct SimpleParser {}
-
-impl Parser for SimpleParser {
-    fn expr(s: &str) -> IResult<&str, u64> {
-        let (rhs, initial) = unary_expr::<Self>(s)?;
-        fold_many0(
-            tuple((
-                preceded(multispace0, operator),
-                preceded(multispace0, unary_expr::<Self>),
-            )),
-            initial,
-            |acc, (op, next)| match op {
-                Operator::Add => acc + next,
-                Operator::Multiply => acc * next,
-            },
-        )(rhs)
-    }
+fn operator<'a>(s: &'a str, precedence: &HashMap<char, u8>) -> IResult<&'a str, (Operator, u8)> {
+    map_opt(preceded(multispace0, anychar), |c| {
+        Some((Operator::from_char(c)?, *precedence.get(&c)?))
+    })(s)
 }
 
-struct AdvancedParser {}
-
-impl AdvancedParser {
-    fn add_expr(s: &str) -> IResult<&str, u64> {
-        let (rhs, initial) = unary_expr::<Self>(s)?;
-        fold_many0(
-            preceded(
-                tuple((multispace0, char('+'), multispace0)),
-                unary_expr::<Self>,
-            ),
-            initial,
-            |acc, next| acc + next,
-        )(rhs)
-    }
+fn unary_expr<'a>(s: &'a str, precedence: &HashMap<char, u8>) -> IResult<&'a str, Expr> {
+    alt((
+        map(number, Expr::Num),
+        delimited(char('('), |s| expr(s, precedence, 0), char(')')),
+    ))(s)
 }
 
-impl Parser for AdvancedParser {
-    fn expr(s: &str) -> IResult<&str, u64> {
-        let (rhs, initial) = Self::add_expr(s)?;
-        fold_many0(
-            preceded(tuple((multispace0, char('*'), multispace0)), Self::add_expr),
-            initial,
-            |acc, next| acc * next,
-        )(rhs)
+fn expr<'a>(s: &'a str, precedence: &HashMap<char, u8>, min_level: u16) -> IResult<&'a str, Expr> {
+    // precedence climbing: operators binding at least as tightly as min_level extend the left
+    // operand, and the right operand only takes operators that bind more tightly, which makes
+    // operators of equal precedence left-associative
+    let (mut rest, mut lhs) = unary_expr(s, precedence)?;
+    loop {
+        let (after_op, (op, level)) = match operator(rest, precedence) {
+            Ok((after_op, (op, level))) if u16::from(level) >= min_level => (after_op, (op, level)),
+            Ok(_) | Err(nom::Err::Error(_)) => return Ok((rest, lhs)),
+            Err(e) => return Err(e),
+        };
+
+        let (after_rhs, rhs) =
+            preceded(multispace0, |s| expr(s, precedence, u16::from(level) + 1))(after_op)?;
+        lhs = Expr::BinOp(Box::new(lhs), op, Box::new(rhs));
+        rest = after_rhs;
     }
 }
 
-pub fn parse(s: &str, use_precedence: bool) -> Result<u64, ParseError> {
-    let expr = if use_precedence {
-        AdvancedParser::expr
-    } else {
-        SimpleParser::expr
-    };
-
-    all_consuming(expr)(s)
+pub fn parse_ast(s: &str, precedence: &HashMap<char, u8>) -> Result<Expr, ParseError> {
+    all_consuming(|s| expr(s, precedence, 0))(s)
         .finish()
-        .map_or_else(|e| Err(ParseError(e.to_string())), |(_, v)| Ok(v))
+        .map(|(_, v)| v)
+        .map_err(|e| {
+            // nom reports the input remaining at the failure, so the offset is what was consumed
+            let remaining = e.input.trim_start();
+            ParseError {
+                message: if remaining.is_empty() {
+                    "unexpected end of input"
+                } else {
+                    "unexpected input"
+                },
+                offset: Some(s.len() - remaining.len()),
+            }
+        })
+}
+
+pub fn parse(s: &str, precedence: &HashMap<char, u8>) -> Result<u64, ParseError> {
+    let ast = parse_ast(s, precedence)?;
+    eval(&ast).ok_or(ParseError {
+        message: "arithmetic overflow or division by zero",
+        offset: None,
+    })
 }
 
 #[cfg(test)]
 mod test {
-    use super::parse;
+    use std::collections::HashMap;
+
+    use super::{eval, parse, parse_ast, Expr, Operator, ADDITION_PRECEDENCE, FLAT_PRECEDENCE};
+
+    fn table(levels: &[(char, u8)]) -> HashMap<char, u8> {
+        levels.iter().copied().collect()
+    }
 
     const EXAMPLES: [(&str, u64, u64); 6] = [
         ("1 + 2 * 3 + 4 * 5 + 6", 71, 231),
@@ -128,7 +158,7 @@ mod test {
     #[test]
     fn expr_test() {
         for &(src, expected, _) in &EXAMPLES {
-            let result = parse(src, false).unwrap();
+            let result = parse(src, &table(&FLAT_PRECEDENCE)).unwrap();
             assert_eq!(result, expected, "Failed on {}", src);
         }
     }
@@ -136,8 +166,140 @@ mod test {
     #[test]
     fn expr_advanced_test() {
         for &(src, _, expected) in &EXAMPLES {
-            let result = parse(src, true).unwrap();
+            let result = parse(src, &table(&ADDITION_PRECEDENCE)).unwrap();
             assert_eq!(result, expected, "Failed on {}", src);
         }
     }
+
+    #[test]
+    fn subtract_divide_test() {
+        const TESTS: [(&str, u64, u64); 5] = [
+            ("10 - 2 * 3", 24, 24),
+            ("2 * 3 - 1", 5, 4),
+            ("12 / 2 + 4", 10, 2),
+            ("8 / 3", 2, 2),
+            ("20 - (3 - 1) / 2", 9, 9),
+        ];
+
+        for &(src, simple, advanced) in &TESTS {
+            assert_eq!(
+                parse(src, &table(&FLAT_PRECEDENCE)).unwrap(),
+                simple,
+                "Failed on {}",
+                src
+            );
+            assert_eq!(
+                parse(src, &table(&ADDITION_PRECEDENCE)).unwrap(),
+                advanced,
+                "Failed on {}",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_arithmetic_test() {
+        for &src in &[
+            "1 / 0",
+            "4 + 2 / (3 - 3)",
+            "3 - 7",
+            "2 * (1 - 2)",
+            "1 +",
+            "1 % 2",
+        ] {
+            assert!(
+                parse(src, &table(&FLAT_PRECEDENCE)).is_err(),
+                "Accepted {}",
+                src
+            );
+            assert!(
+                parse(src, &table(&ADDITION_PRECEDENCE)).is_err(),
+                "Accepted {}",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn custom_precedence_test() {
+        let precedence = table(&[('+', 1), ('-', 1), ('*', 2), ('/', 2)]);
+        const TESTS: [(&str, u64); 5] = [
+            ("1 + 2 * 3 + 4 * 5 + 6", 33),
+            ("2 * 3 + (4 * 5)", 26),
+            ("10 - 2 * 3", 4),
+            ("10 - 3 - 2", 5),
+            ("100 / 10 / 5", 2),
+        ];
+        for &(src, expected) in &TESTS {
+            let result = parse(src, &precedence).unwrap();
+            assert_eq!(result, expected, "Failed on {}", src);
+        }
+
+        let precedence = table(&[('+', 1), ('*', 2)]);
+        assert_eq!(parse("1 + 2 * 3", &precedence).unwrap(), 7);
+        assert!(parse("3 - 2", &precedence).is_err());
+    }
+
+    fn binop(lhs: Expr, op: Operator, rhs: Expr) -> Expr {
+        Expr::BinOp(Box::new(lhs), op, Box::new(rhs))
+    }
+
+    #[test]
+    fn ast_test() {
+        let expected = binop(
+            binop(Expr::Num(1), Operator::Add, Expr::Num(2)),
+            Operator::Multiply,
+            Expr::Num(3),
+        );
+        for levels in &[FLAT_PRECEDENCE, ADDITION_PRECEDENCE] {
+            let ast = parse_ast("1 + 2 * 3", &table(levels)).unwrap();
+            assert_eq!(ast, expected);
+            assert_eq!(eval(&ast), Some(9));
+        }
+
+        let ast = parse_ast("1 + 2 * 3", &table(&[('+', 1), ('*', 2)])).unwrap();
+        let expected = binop(
+            Expr::Num(1),
+            Operator::Add,
+            binop(Expr::Num(2), Operator::Multiply, Expr::Num(3)),
+        );
+        assert_eq!(ast, expected);
+        assert_eq!(eval(&ast), Some(7));
+
+        let ast = parse_ast("2 * (3 - 5)", &table(&FLAT_PRECEDENCE)).unwrap();
+        let expected = binop(
+            Expr::Num(2),
+            Operator::Multiply,
+            binop(Expr::Num(3), Operator::Subtract, Expr::Num(5)),
+        );
+        assert_eq!(ast, expected);
+        assert_eq!(eval(&ast), None);
+    }
+
+    #[test]
+    fn error_column_test() {
+        let precedence = table(&FLAT_PRECEDENCE);
+        let error = parse("1 + * 2", &precedence).unwrap_err();
+        assert_eq!(error.offset, Some(4));
+        assert_eq!(
+            error.to_string(),
+            "Parse error: unexpected input at column 5"
+        );
+
+        let error = parse("(1 + 2) )", &precedence).unwrap_err();
+        assert_eq!(error.offset, Some(8));
+        let error = parse("2 * (3 + 4", &precedence).unwrap_err();
+        assert_eq!(error.offset, Some(10));
+        assert_eq!(
+            error.to_string(),
+            "Parse error: unexpected end of input at column 11"
+        );
+
+        let error = parse("1 / 0", &precedence).unwrap_err();
+        assert_eq!(error.offset, None);
+        assert_eq!(
+            error.to_string(),
+            "Parse error: arithmetic overflow or division by zero"
+        );
+    }
 }
@@ -25,7 +25,6 @@ pub enum ParseRule {
     Character(char),
     Sequence(Vec<u32>),
     Alternative(Vec<u32>, Vec<u32>),
-    MoreOfFirst(u32, u32),
 }
 
 #[derive(Debug)]
@@ -48,54 +47,126 @@ fn parse_char(c: char, s: &str) -> Option<&str> {
     }
 }
 
-fn parse_seq<'a>(rule_map: &RuleMap, seq: &[u32], s: &'a str) -> Option<&'a str> {
-    let mut remaining = s;
-    for sub_rule in seq {
-        match test_rule(rule_map, *sub_rule, remaining) {
-            Some(r) => remaining = r,
-            _ => return None,
+// number of ways of matching each rule against each suffix of a message, keyed by rule and
+// suffix length, and stored as pairs of remaining suffix length and count
+type MatchCache = AHashMap<(u32, usize), Vec<(usize, usize)>>;
+
+fn merge_counts(counts: &mut Vec<(usize, usize)>) {
+    counts.sort_unstable_by_key(|&(len, _)| len);
+    counts.dedup_by(|(len, count), (prev_len, prev_count)| {
+        if len == prev_len {
+            *prev_count = prev_count.saturating_add(*count);
+            true
+        } else {
+            false
         }
-    }
-
-    Some(remaining)
+    });
 }
 
-fn parse_more_first<'a>(rule_map: &RuleMap, start: u32, end: u32, s: &'a str) -> Option<&'a str> {
-    let mut remaining = s;
-    let mut count: usize = 0;
-    while let Some(r) = test_rule(rule_map, start, remaining) {
-        remaining = r;
-        count += 1;
-    }
-
-    if count < 2 {
-        return None;
-    }
+// returns None after adding any sub-rule matches that need to be computed first to `missing`
+fn match_seq(
+    seq: &[u32],
+    len: usize,
+    cache: &MatchCache,
+    missing: &mut Vec<(u32, usize)>,
+) -> Option<Vec<(usize, usize)>> {
+    let mut remaining = vec![(len, 1usize)];
+    for sub_rule in seq {
+        let mut next = Vec::new();
+        for &(len, count) in &remaining {
+            let matches = match cache.get(&(*sub_rule, len)) {
+                Some(matches) => matches,
+                None => {
+                    missing.push((*sub_rule, len));
+                    continue;
+                }
+            };
+
+            next.extend(matches.iter().map(|&(r, c)| (r, count.saturating_mul(c))));
+        }
 
-    for i in 0..count - 1 {
-        match test_rule(rule_map, end, remaining) {
-            Some(r) => remaining = r,
-            None if i == 0 => return None,
-            None => break,
+        if !missing.is_empty() {
+            return None;
         }
+        merge_counts(&mut next);
+        remaining = next;
     }
 
     Some(remaining)
 }
 
-fn test_rule<'a>(rule_map: &RuleMap, rule_id: u32, s: &'a str) -> Option<&'a str> {
-    match rule_map.0.get(&rule_id).unwrap() {
-        ParseRule::Character(c) => parse_char(*c, s),
-        ParseRule::Sequence(seq) => parse_seq(rule_map, seq, s),
-        ParseRule::Alternative(seq1, seq2) => {
-            parse_seq(rule_map, seq1, s).or_else(|| parse_seq(rule_map, seq2, s))
+fn match_rule_cached(rule_map: &RuleMap, rule_id: u32, s: &str, cache: &mut MatchCache) {
+    // recursive rules nest once for every repetition, so keep the pending matches on an explicit
+    // stack rather than recursing through them
+    let mut stack = vec![(rule_id, s.len())];
+    let mut missing = Vec::new();
+    while let Some(&(id, len)) = stack.last() {
+        if cache.contains_key(&(id, len)) {
+            stack.pop();
+            continue;
         }
-        ParseRule::MoreOfFirst(start, end) => parse_more_first(rule_map, *start, *end, s),
+
+        let result = match rule_map.0.get(&id).unwrap() {
+            ParseRule::Character(c) => parse_char(*c, &s[s.len() - len..])
+                .map(|r| (r.len(), 1))
+                .into_iter()
+                .collect(),
+            ParseRule::Sequence(seq) => match match_seq(seq, len, cache, &mut missing) {
+                Some(result) => result,
+                None => {
+                    stack.append(&mut missing);
+                    continue;
+                }
+            },
+            ParseRule::Alternative(seq1, seq2) => {
+                let result1 = match_seq(seq1, len, cache, &mut missing);
+                let result2 = match_seq(seq2, len, cache, &mut missing);
+                match result1.zip(result2) {
+                    Some((mut result, result2)) => {
+                        result.extend(result2);
+                        merge_counts(&mut result);
+                        result
+                    }
+                    None => {
+                        stack.append(&mut missing);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        cache.insert((id, len), result);
+        stack.pop();
     }
 }
 
+fn match_rule<'a>(rule_map: &RuleMap, rule_id: u32, s: &'a str) -> Vec<&'a str> {
+    let mut cache = MatchCache::default();
+    match_rule_cached(rule_map, rule_id, s, &mut cache);
+    cache[&(rule_id, s.len())]
+        .iter()
+        .map(|&(len, _)| &s[s.len() - len..])
+        .collect()
+}
+
+fn count_parses(rule_map: &RuleMap, s: &str) -> usize {
+    let mut cache = MatchCache::default();
+    match_rule_cached(rule_map, 0, s, &mut cache);
+    cache[&(0, s.len())]
+        .iter()
+        .find(|&&(len, _)| len == 0)
+        .map_or(0, |&(_, count)| count)
+}
+
 fn test_rules(rule_map: &RuleMap, s: &str) -> bool {
-    matches!(test_rule(rule_map, 0, s), Some(r) if r.is_empty())
+    match_rule(rule_map, 0, s).iter().any(|r| r.is_empty())
+}
+
+fn count_ambiguous(rule_map: &RuleMap, messages: &[impl AsRef<str>]) -> usize {
+    messages
+        .iter()
+        .filter(|m| count_parses(rule_map, m.as_ref()) > 1)
+        .count()
 }
 
 mod rule_parsing {
@@ -152,10 +223,10 @@ impl RuleMap {
     }
 
     fn update_rules(&mut self) {
-        self.0.insert(0, ParseRule::MoreOfFirst(42, 31));
-        // not implemented individually, remove them to trigger a panic if they get referenced.
-        self.0.remove(&8);
-        self.0.remove(&11);
+        self.0
+            .insert(8, ParseRule::Alternative(vec![42], vec![42, 8]));
+        self.0
+            .insert(11, ParseRule::Alternative(vec![42, 31], vec![42, 11, 31]));
     }
 }
 
@@ -163,7 +234,7 @@ fn read_file(path: impl AsRef<Path>) -> Result<(RuleMap, Vec<String>), Box<dyn E
     enum ReadState {
         Rules,
         Messages,
-    };
+    }
     let file = File::open(path)?;
     let mut rule_map = RuleMap::default();
     let mut messages = Vec::new();
@@ -196,6 +267,10 @@ fn run() -> Result<(), Box<dyn Error>> {
 
     let part2 = messages.iter().filter(|m| test_rules(&rule_map, m)).count();
     println!("Part 2: valid count = {}", part2);
+    println!(
+        "Part 2: ambiguous count = {}",
+        count_ambiguous(&rule_map, &messages)
+    );
 
     Ok(())
 }
@@ -212,7 +287,7 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{test_rules, RuleMap};
+    use super::{count_ambiguous, count_parses, match_rule, test_rules, RuleMap};
 
     const PART1_RULES: &str = r#"0: 4 1 5
 1: 2 3 | 3 2
@@ -303,5 +378,97 @@ mod test {
             let result = test_rules(&rule_map, message);
             assert_eq!(result, expected, "message {} failed", message);
         }
+
+        let valid = PART2_TESTS
+            .iter()
+            .filter(|(m, _)| test_rules(&rule_map, m))
+            .count();
+        assert_eq!(valid, 12);
+    }
+
+    #[test]
+    fn long_message_test() {
+        let mut rule_map = RuleMap::default();
+        PART2_RULES
+            .lines()
+            .for_each(|line| rule_map.try_add_rule(line).unwrap());
+        rule_map.update_rules();
+
+        // bbabbbbaabaabba is two matches of rule 42 followed by one of rule 31
+        let valid = format!("{}{}", "bbabb".repeat(2000), "aabba".repeat(1000));
+        assert!(test_rules(&rule_map, &valid));
+        let invalid = format!("{}{}", "bbabb".repeat(1000), "aabba".repeat(1000));
+        assert!(!test_rules(&rule_map, &invalid));
+    }
+
+    #[test]
+    fn match_rule_test() {
+        let mut rule_map = RuleMap::default();
+        PART1_RULES
+            .lines()
+            .for_each(|line| rule_map.try_add_rule(line).unwrap());
+        assert_eq!(match_rule(&rule_map, 1, "aaabb"), vec!["b"]);
+        let mut result = match_rule(&rule_map, 3, "abba");
+        result.sort_unstable();
+        assert_eq!(result, vec!["ba"]);
+        assert!(match_rule(&rule_map, 2, "ab").is_empty());
+        let valid = PART1_TESTS
+            .iter()
+            .filter(|(m, _)| test_rules(&rule_map, m))
+            .count();
+        assert_eq!(valid, 2);
+
+        let mut rule_map = RuleMap::default();
+        PART2_RULES
+            .lines()
+            .for_each(|line| rule_map.try_add_rule(line).unwrap());
+        let valid = PART2_TESTS
+            .iter()
+            .filter(|(m, _)| test_rules(&rule_map, m))
+            .count();
+        assert_eq!(valid, 3);
+
+        rule_map.update_rules();
+        // rule 8 can stop after either of the two leading matches of rule 42
+        let mut result = match_rule(&rule_map, 8, "bbabbbbaabaabba");
+        result.sort_unstable();
+        assert_eq!(result, vec!["aabba", "bbaabaabba"]);
+        for &(message, expected) in &PART2_TESTS {
+            let result = test_rules(&rule_map, message);
+            assert_eq!(result, expected, "message {} failed", message);
+        }
+        let valid = PART2_TESTS
+            .iter()
+            .filter(|(m, _)| test_rules(&rule_map, m))
+            .count();
+        assert_eq!(valid, 12);
+    }
+
+    #[test]
+    fn ambiguous_test() {
+        let mut rule_map = RuleMap::default();
+        ["0: 1 1", "1: 2 | 2 2", r#"2: "a""#]
+            .iter()
+            .for_each(|line| rule_map.try_add_rule(line).unwrap());
+
+        // aaa splits as a + aa or aa + a, but aaaa can only be aa + aa
+        let messages = ["a", "aa", "aaa", "aaaa", "aaaaa"];
+        let counts = messages
+            .iter()
+            .map(|m| count_parses(&rule_map, m))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![0, 1, 2, 1, 0]);
+        assert_eq!(count_ambiguous(&rule_map, &messages), 1);
+
+        let mut rule_map = RuleMap::default();
+        PART2_RULES
+            .lines()
+            .for_each(|line| rule_map.try_add_rule(line).unwrap());
+        rule_map.update_rules();
+        let messages = PART2_TESTS.iter().map(|(m, _)| *m).collect::<Vec<_>>();
+        for &(message, expected) in &PART2_TESTS {
+            assert_eq!(count_parses(&rule_map, message) > 0, expected);
+        }
+        assert_eq!(count_ambiguous(&rule_map, &messages), 0);
     }
 }
@@ -220,4 +220,22 @@ mod test {
 
         assert!(found_result);
     }
+
+    #[test]
+    fn display_test() {
+        let grid = Grid::parse(EXAMPLE_DATA.lines()).unwrap();
+        let mut result = grid.merge_tiles();
+        for i in 0..8 {
+            if result.to_string() == EXAMPLE_MERGED {
+                break;
+            }
+
+            result.rotate_right();
+            if i == 3 {
+                result.flip_horizontal();
+            }
+        }
+
+        assert_eq!(result.to_string(), EXAMPLE_MERGED);
+    }
 }
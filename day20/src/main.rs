@@ -13,12 +13,13 @@ mod tile;
 mod utils;
 
 use grid::Grid;
+use tile::SEA_MONSTER;
 
 fn run() -> Result<(), Box<dyn Error>> {
     let grid = {
         let path = ["data", "day20", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
-        Grid::parse(BufReader::new(file).lines().filter_map(Result::ok))?
+        Grid::parse(BufReader::new(file).lines().map_while(Result::ok))?
     };
 
     println!(
@@ -27,7 +28,7 @@ fn run() -> Result<(), Box<dyn Error>> {
     );
 
     let mut merged = grid.merge_tiles();
-    merged.remove_monsters();
+    merged.remove_monsters(&SEA_MONSTER);
     println!("Part 2: rougness = {}", merged.roughness());
 
     Ok(())
@@ -3,16 +3,15 @@ use std::{error::Error, fmt};
 use crate::utils::sqrt_exact;
 
 lazy_static! {
-    static ref MONSTER_PATTERN: Vec<Vec<bool>> = [
-        "                  # ",
-        "#    ##    ##    ###",
-        " #  #  #  #  #  #   "
-    ]
-    .iter()
-    .map(|line| line.chars().map(|c| c == '#').collect())
-    .collect();
-    static ref MONSTER_HEIGHT: usize = MONSTER_PATTERN.len();
-    static ref MONSTER_WIDTH: usize = MONSTER_PATTERN[0].len();
+    pub static ref SEA_MONSTER: Monster = Monster::parse(
+        [
+            "                  # ",
+            "#    ##    ##    ###",
+            " #  #  #  #  #  #   ",
+        ]
+        .iter()
+    )
+    .unwrap();
 }
 
 #[derive(Debug)]
@@ -60,6 +59,36 @@ fn update_line(row: &mut [bool], monster_row: &[bool]) {
         .for_each(|(t, m)| *t &= !m);
 }
 
+#[derive(Debug, Clone)]
+pub struct Monster {
+    pattern: Vec<Vec<bool>>,
+    width: usize,
+}
+
+impl Monster {
+    pub fn parse<S, I>(lines: I) -> Result<Self, ParseTileError>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+    {
+        let pattern = lines
+            .map(|line| line.as_ref().chars().map(|c| c == '#').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let width = pattern.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(ParseTileError("Empty monster pattern"));
+        } else if pattern.iter().any(|row| row.len() != width) {
+            return Err(ParseTileError("Inconsistent monster pattern width"));
+        }
+
+        Ok(Self { pattern, width })
+    }
+
+    fn height(&self) -> usize {
+        self.pattern.len()
+    }
+}
+
 #[derive(Debug)]
 enum HorizontalEdge {
     Left,
@@ -74,36 +103,37 @@ enum VerticalEdge {
 
 #[derive(Debug)]
 pub struct EdgeConstraints {
-    left: Option<u32>,
-    right: Option<u32>,
-    top: Option<u32>,
-    bottom: Option<u32>,
+    left: Option<u64>,
+    right: Option<u64>,
+    top: Option<u64>,
+    bottom: Option<u64>,
 }
 
 impl EdgeConstraints {
-    pub fn right(value: u32) -> Self {
+    pub fn right(value: u64) -> Self {
         Self {
             right: Some(value),
             ..Default::default()
         }
     }
 
-    pub fn and_left(&mut self, value: u32) -> &Self {
+    pub fn and_left(&mut self, value: u64) -> &Self {
         self.left = Some(value);
         self
     }
 
-    pub fn and_top(&mut self, value: u32) -> &Self {
+    pub fn and_top(&mut self, value: u64) -> &Self {
         self.top = Some(value);
         self
     }
 
-    pub fn and_bottom(&mut self, value: u32) -> &Self {
+    pub fn and_bottom(&mut self, value: u64) -> &Self {
         self.bottom = Some(value);
         self
     }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for EdgeConstraints {
     fn default() -> Self {
         Self {
@@ -133,8 +163,8 @@ impl Tile {
         let size = first_row.len();
         if size == 0 {
             return Err(ParseTileError("Empty tile"));
-        } else if size > 32 {
-            return Err(ParseTileError("Tiles larger than 32x32 not supported"));
+        } else if size > 64 {
+            return Err(ParseTileError("Tiles larger than 64x64 not supported"));
         }
 
         let mut data = TileData::with_capacity(size * size);
@@ -191,7 +221,7 @@ impl Tile {
         &self.data
     }
 
-    fn row_fwd(&self, edge: VerticalEdge) -> u32 {
+    fn row_fwd(&self, edge: VerticalEdge) -> u64 {
         let row_start = match edge {
             VerticalEdge::Top => 0,
             VerticalEdge::Bottom => self.data.len() - self.size,
@@ -199,10 +229,10 @@ impl Tile {
 
         self.data[row_start..row_start + self.size]
             .iter()
-            .fold(0, |acc, &b| (acc << 1) + b as u32)
+            .fold(0, |acc, &b| (acc << 1) + u64::from(b))
     }
 
-    fn row_rev(&self, edge: VerticalEdge) -> u32 {
+    fn row_rev(&self, edge: VerticalEdge) -> u64 {
         let row_start = match edge {
             VerticalEdge::Top => 0,
             VerticalEdge::Bottom => self.data.len() - self.size,
@@ -211,10 +241,10 @@ impl Tile {
         self.data[row_start..row_start + self.size]
             .iter()
             .rev()
-            .fold(0, |acc, &b| (acc << 1) + b as u32)
+            .fold(0, |acc, &b| (acc << 1) + u64::from(b))
     }
 
-    fn col_fwd(&self, edge: HorizontalEdge) -> u32 {
+    fn col_fwd(&self, edge: HorizontalEdge) -> u64 {
         let col = match edge {
             HorizontalEdge::Left => 0,
             HorizontalEdge::Right => self.size - 1,
@@ -223,10 +253,10 @@ impl Tile {
         self.data[col..]
             .iter()
             .step_by(self.size)
-            .fold(0, |acc, &b| (acc << 1) + b as u32)
+            .fold(0, |acc, &b| (acc << 1) + u64::from(b))
     }
 
-    fn col_rev(&self, edge: HorizontalEdge) -> u32 {
+    fn col_rev(&self, edge: HorizontalEdge) -> u64 {
         let col = match edge {
             HorizontalEdge::Left => 0,
             HorizontalEdge::Right => self.size - 1,
@@ -236,18 +266,18 @@ impl Tile {
             .iter()
             .step_by(self.size)
             .rev()
-            .fold(0, |acc, &b| (acc << 1) + b as u32)
+            .fold(0, |acc, &b| (acc << 1) + u64::from(b))
     }
 
-    pub fn right_edge(&self) -> u32 {
+    pub fn right_edge(&self) -> u64 {
         self.col_fwd(HorizontalEdge::Right)
     }
 
-    pub fn bottom_edge(&self) -> u32 {
+    pub fn bottom_edge(&self) -> u64 {
         self.row_fwd(VerticalEdge::Bottom)
     }
 
-    pub fn connect(&self, other: &Tile) -> Vec<u32> {
+    pub fn connect(&self, other: &Tile) -> Vec<u64> {
         let edges = [
             self.row_fwd(VerticalEdge::Top),
             self.row_fwd(VerticalEdge::Bottom),
@@ -277,6 +307,8 @@ impl Tile {
             .collect()
     }
 
+    // Option::is_none_or would need Rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
     pub fn orient(&mut self, constraints: &EdgeConstraints) -> bool {
         use HorizontalEdge::{Left, Right};
         use VerticalEdge::{Bottom, Top};
@@ -316,17 +348,21 @@ impl Tile {
         }
     }
 
-    pub fn remove_monsters(&mut self) {
+    pub fn remove_monsters(&mut self, monster: &Monster) {
+        if monster.height() > self.size || monster.width > self.size {
+            return;
+        }
+
         for i in 0..8 {
             let mut found_monsters = false;
             let mut rows = self.data.chunks_mut(self.size).collect::<Vec<_>>();
-            for y in 0..rows.len() - *MONSTER_HEIGHT {
-                let row_slice = &mut rows[y..y + *MONSTER_HEIGHT];
-                for x in 0..self.size - *MONSTER_WIDTH {
+            for y in 0..=rows.len() - monster.height() {
+                let row_slice = &mut rows[y..y + monster.height()];
+                for x in 0..=self.size - monster.width {
                     let has_monster = row_slice
                         .iter()
-                        .map(|r| &r[x..x + *MONSTER_WIDTH])
-                        .zip(MONSTER_PATTERN.iter())
+                        .map(|r| &r[x..x + monster.width])
+                        .zip(monster.pattern.iter())
                         .all(|(row, monster_row)| check_line(row, monster_row));
 
                     if !has_monster {
@@ -336,8 +372,8 @@ impl Tile {
                     found_monsters = true;
                     row_slice
                         .iter_mut()
-                        .map(|r| &mut r[x..x + *MONSTER_WIDTH])
-                        .zip(MONSTER_PATTERN.iter())
+                        .map(|r| &mut r[x..x + monster.width])
+                        .zip(monster.pattern.iter())
                         .for_each(|(row, monster_row)| update_line(row, monster_row));
                 }
             }
@@ -358,6 +394,22 @@ impl Tile {
     }
 }
 
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.data.chunks(self.size).enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for &element in row {
+                write!(f, "{}", if element { '#' } else { '.' })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub fn parse_tiles<S, I>(mut lines: I) -> Result<Vec<Tile>, ParseTileError>
 where
     S: AsRef<str>,
@@ -388,7 +440,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_tiles, Tile};
+    use super::{parse_tiles, EdgeConstraints, Monster, Tile, SEA_MONSTER};
 
     const EXAMPLE_DATA: &str = include_str!("test_input.txt");
     const EXAMPLE_IDS: [u64; 9] = [2311, 1951, 1171, 1427, 1489, 2473, 2971, 2729, 3079];
@@ -490,9 +542,100 @@ mod tests {
         )
         .unwrap();
 
-        tile.remove_monsters();
+        tile.remove_monsters(&SEA_MONSTER);
 
         let roughness = tile.roughness();
         assert_eq!(roughness, 273);
     }
+
+    #[test]
+    fn display_test() {
+        let tile = Tile {
+            id: 0,
+            size: 3,
+            data: vec![true, false, true, true, true, false, false, false, true],
+        };
+        assert_eq!(tile.to_string(), "#.#\n##.\n..#");
+
+        let mut tile = Tile::from_data(
+            EXAMPLE_MONSTERS
+                .lines()
+                .flat_map(|s| s.chars().map(|c| c == '#'))
+                .collect::<Vec<_>>()
+                .as_slice(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(tile.to_string(), EXAMPLE_MONSTERS);
+
+        // removed monsters leave blank squares, and the rows keep the tile width
+        tile.remove_monsters(&SEA_MONSTER);
+        let image = tile.to_string();
+        assert_eq!(image.matches('#').count(), 273);
+        assert!(image.lines().all(|line| line.len() == 24));
+        assert_eq!(image.lines().count(), 24);
+    }
+
+    #[test]
+    fn custom_monster_test() {
+        let monster = Monster::parse(["##", "##"].iter()).unwrap();
+        let mut tile = Tile::from_data(
+            ["##...", "##..#", ".....", "..##.", "..##."]
+                .iter()
+                .flat_map(|s| s.chars().map(|c| c == '#'))
+                .collect::<Vec<_>>()
+                .as_slice(),
+            0,
+        )
+        .unwrap();
+
+        tile.remove_monsters(&monster);
+        assert_eq!(tile.roughness(), 1);
+
+        // the diagonal only appears after rotating
+        let monster = Monster::parse(["#.", ".#"].iter()).unwrap();
+        let mut tile = Tile::from_data(&[false, true, true, true], 0).unwrap();
+        tile.remove_monsters(&monster);
+        assert_eq!(tile.roughness(), 1);
+
+        assert!(Monster::parse(std::iter::empty::<&str>()).is_err());
+        assert!(Monster::parse(["##", "#"].iter()).is_err());
+    }
+
+    #[test]
+    fn large_tile_test() {
+        // an irregular pattern, so that no two edges of the tile coincide
+        let lines = (0..40)
+            .map(|y| {
+                (0..40)
+                    .map(|x| {
+                        if (3 * x * x + 5 * y * y + x * y) % 11 < 5 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        let tile = Tile::parse(&mut lines.iter(), 1).unwrap();
+        assert_eq!(tile.size(), 40);
+
+        // mirroring the lines gives a tile whose left edge matches the right edge of the original
+        let mirrored = lines
+            .iter()
+            .map(|line| line.chars().rev().collect::<String>())
+            .collect::<Vec<_>>();
+        let mut other = Tile::parse(&mut mirrored.iter(), 2).unwrap();
+        assert!(tile.right_edge() > u64::from(u32::MAX));
+        assert!(tile.connect(&other).contains(&tile.right_edge()));
+
+        other.rotate_right();
+        assert!(other.orient(EdgeConstraints::default().and_left(tile.right_edge())));
+        other.flip_horizontal();
+        assert_eq!(other.right_edge(), tile.right_edge());
+
+        let too_large = vec!["#".repeat(65); 65];
+        assert!(Tile::parse(&mut too_large.iter(), 3).is_err());
+    }
 }
@@ -12,7 +12,7 @@ use food::FoodProcessor;
 fn run() -> Result<(), Box<dyn Error>> {
     let path = ["data", "day21", "input.txt"].iter().collect::<PathBuf>();
     let file = File::open(path)?;
-    let processor = FoodProcessor::parse(BufReader::new(file).lines().filter_map(Result::ok))?;
+    let processor = FoodProcessor::parse(BufReader::new(file).lines().map_while(Result::ok))?;
     println!("Part 1: result = {}", processor.safe_count());
     println!("Part 2: result = {}", processor.map_allergens());
     Ok(())
@@ -1,6 +1,7 @@
 use std::{
     collections::VecDeque,
     error::Error,
+    fmt,
     fs::File,
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
@@ -8,12 +9,55 @@ use std::{
 
 use ahash::AHashSet;
 
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Parse error: {}", self.0)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug)]
+pub struct RoundLimitError(u64);
+
+impl fmt::Display for RoundLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game exceeded the limit of {} rounds", self.0)
+    }
+}
+
+impl Error for RoundLimitError {}
+
+const MAX_ROUNDS: u64 = 100_000_000;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Player {
     Player1,
     Player2,
 }
 
+#[derive(Debug)]
+struct Frame {
+    player1: VecDeque<u64>,
+    player2: VecDeque<u64>,
+    previous_rounds: AHashSet<(VecDeque<u64>, VecDeque<u64>)>,
+    drawn: Option<(u64, u64)>,
+}
+
+impl Frame {
+    fn new(player1: VecDeque<u64>, player2: VecDeque<u64>) -> Self {
+        Self {
+            player1,
+            player2,
+            previous_rounds: AHashSet::new(),
+            drawn: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Game {
     player1: VecDeque<u64>,
@@ -32,9 +76,12 @@ impl Game {
 
     pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
         let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, Box<dyn Error>> {
         let mut player1 = VecDeque::new();
         let mut player2 = VecDeque::new();
-        let lines = BufReader::new(file).lines();
 
         #[derive(Debug)]
         enum ParseState {
@@ -43,7 +90,7 @@ impl Game {
         }
 
         let mut state = ParseState::Player1;
-        for line_result in lines {
+        for (line_number, line_result) in reader.lines().enumerate() {
             let line = line_result?;
 
             match line.as_str() {
@@ -51,7 +98,13 @@ impl Game {
                 "Player 1:" => state = ParseState::Player1,
                 "Player 2:" => state = ParseState::Player2,
                 _ => {
-                    let value = line.parse()?;
+                    let value = line.parse().map_err(|_| {
+                        ParseError(format!(
+                            "invalid card value {:?} on line {}",
+                            line,
+                            line_number + 1
+                        ))
+                    })?;
                     match state {
                         ParseState::Player1 => player1.push_back(value),
                         ParseState::Player2 => player2.push_back(value),
@@ -127,20 +180,105 @@ impl Game {
         }
     }
 
-    pub fn winning_score(&self) -> Option<u64> {
-        let winning_deck = self.winner.map(|p| match p {
+    pub fn play_recursive_stack(&mut self) {
+        self.play_recursive_limited(u64::MAX).unwrap();
+    }
+
+    // limits the total number of rounds played across the game and all its sub-games, on error
+    // the decks are left in an unspecified state
+    pub fn play_recursive_limited(&mut self, max_rounds: u64) -> Result<(), RoundLimitError> {
+        let mut rounds = 0;
+        let mut stack = vec![Frame::new(
+            std::mem::take(&mut self.player1),
+            std::mem::take(&mut self.player2),
+        )];
+        let mut sub_game_winner = None;
+
+        loop {
+            let frame = stack.last_mut().unwrap();
+            let round_winner = match sub_game_winner.take() {
+                Some(winner) => winner,
+                None => {
+                    let game_winner = if frame.player1.is_empty() {
+                        Some(Player::Player2)
+                    } else if frame.player2.is_empty()
+                        || !frame
+                            .previous_rounds
+                            .insert((frame.player1.clone(), frame.player2.clone()))
+                    {
+                        Some(Player::Player1)
+                    } else {
+                        None
+                    };
+
+                    if let Some(winner) = game_winner {
+                        let finished = stack.pop().unwrap();
+                        if stack.is_empty() {
+                            self.player1 = finished.player1;
+                            self.player2 = finished.player2;
+                            self.winner = Some(winner);
+                            return Ok(());
+                        }
+                        sub_game_winner = Some(winner);
+                        continue;
+                    }
+
+                    rounds += 1;
+                    if rounds > max_rounds {
+                        return Err(RoundLimitError(max_rounds));
+                    }
+
+                    let card1 = frame.player1.pop_front().unwrap();
+                    let card2 = frame.player2.pop_front().unwrap();
+                    frame.drawn = Some((card1, card2));
+
+                    if frame.player1.len() as u64 >= card1 && frame.player2.len() as u64 >= card2 {
+                        let sub_game = Frame::new(
+                            frame.player1.iter().take(card1 as usize).copied().collect(),
+                            frame.player2.iter().take(card2 as usize).copied().collect(),
+                        );
+                        stack.push(sub_game);
+                        continue;
+                    } else if card1 > card2 {
+                        Player::Player1
+                    } else {
+                        Player::Player2
+                    }
+                }
+            };
+
+            let (card1, card2) = frame.drawn.take().unwrap();
+            match round_winner {
+                Player::Player1 => {
+                    frame.player1.push_back(card1);
+                    frame.player1.push_back(card2);
+                }
+                Player::Player2 => {
+                    frame.player2.push_back(card2);
+                    frame.player2.push_back(card1);
+                }
+            }
+        }
+    }
+
+    pub fn result(&self) -> Option<(Player, &VecDeque<u64>, u64)> {
+        let winner = self.winner?;
+        let winning_deck = match winner {
             Player::Player1 => &self.player1,
             Player::Player2 => &self.player2,
-        })?;
+        };
 
         let length = winning_deck.len();
-        Some(
-            winning_deck
-                .iter()
-                .enumerate()
-                .map(|(i, card)| card * ((length - i) as u64))
-                .sum(),
-        )
+        let score = winning_deck
+            .iter()
+            .enumerate()
+            .map(|(i, card)| card * ((length - i) as u64))
+            .sum();
+        Some((winner, winning_deck, score))
+    }
+
+    pub fn winning_score(&self) -> Option<u64> {
+        self.result().map(|(_, _, score)| score)
     }
 }
 
@@ -154,7 +292,7 @@ fn run() -> Result<(), Box<dyn Error>> {
     game1.play();
     println!("Part 1: score = {}", game1.winning_score().unwrap());
 
-    game2.play_recursive();
+    game2.play_recursive_limited(MAX_ROUNDS)?;
     println!("Part 2: score = {}", game2.winning_score().unwrap());
 
     Ok(())
@@ -172,8 +310,12 @@ fn main() {
 
 #[cfg(test)]
 mod test {
+    use std::io::Cursor;
+
     use super::{Game, Player};
 
+    const EXAMPLE: &str = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10\n";
+
     #[test]
     fn part1_test() {
         let mut game = Game::new(
@@ -196,4 +338,88 @@ mod test {
         let result = game.winning_score();
         assert_eq!(result, Some(291));
     }
+
+    #[test]
+    fn part2_stack_test() {
+        let mut game = Game::new(
+            [9, 2, 6, 3, 1].iter().copied().collect(),
+            [5, 8, 4, 7, 10].iter().copied().collect(),
+        );
+        game.play_recursive_stack();
+        assert!(matches!(game.winner, Some(Player::Player2)));
+        let result = game.winning_score();
+        assert_eq!(result, Some(291));
+    }
+
+    #[test]
+    fn round_limit_test() {
+        let mut game = Game::new(
+            [9, 2, 6, 3, 1].iter().copied().collect(),
+            [5, 8, 4, 7, 10].iter().copied().collect(),
+        );
+        let mut limited = game.clone();
+        let error = limited.play_recursive_limited(10).unwrap_err();
+        assert_eq!(error.to_string(), "Game exceeded the limit of 10 rounds");
+        assert!(limited.winner.is_none());
+
+        assert!(game.play_recursive_limited(1000).is_ok());
+        assert_eq!(game.winning_score(), Some(291));
+    }
+
+    #[test]
+    fn result_test() {
+        let mut game = Game::new(
+            [9, 2, 6, 3, 1].iter().copied().collect(),
+            [5, 8, 4, 7, 10].iter().copied().collect(),
+        );
+        game.play_recursive();
+        let (winner, deck, score) = game.result().unwrap();
+        assert!(matches!(winner, Player::Player2));
+        assert!(deck.iter().eq([7, 5, 6, 2, 4, 1, 10, 8, 9, 3].iter()));
+        assert_eq!(score, 291);
+    }
+
+    #[test]
+    fn from_reader_test() {
+        let mut game = Game::from_reader(Cursor::new(EXAMPLE)).unwrap();
+        game.play();
+        assert_eq!(game.winning_score(), Some(306));
+    }
+
+    #[test]
+    fn from_reader_error_test() {
+        let input = "Player 1:\n9\n2\nsix\n\nPlayer 2:\n5\n8\n";
+        let error = Game::from_reader(Cursor::new(input)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parse error: invalid card value \"six\" on line 4"
+        );
+    }
+
+    #[test]
+    fn stack_parity_test() {
+        // the documented examples, including the one that loops, plus a few hand-picked decks
+        let decks: [(&[u64], &[u64]); 6] = [
+            (&[9, 2, 6, 3, 1], &[5, 8, 4, 7, 10]),
+            (&[5, 8, 4, 7, 10], &[9, 2, 6, 3, 1]),
+            (&[43, 19], &[2, 29, 14]),
+            (&[1], &[2]),
+            (&[3, 1, 2, 9], &[4, 5, 6, 7, 8]),
+            (&[2, 7, 1, 10, 4, 6], &[3, 9, 5, 8]),
+        ];
+
+        for (deck1, deck2) in decks.iter() {
+            let mut recursive = Game::new(
+                deck1.iter().copied().collect(),
+                deck2.iter().copied().collect(),
+            );
+            let mut stack = recursive.clone();
+            recursive.play_recursive();
+            stack.play_recursive_stack();
+
+            assert_eq!(recursive.player1, stack.player1);
+            assert_eq!(recursive.player2, stack.player2);
+            assert_eq!(recursive.winning_score(), stack.winning_score());
+        }
+    }
 }
@@ -1,113 +1,405 @@
+use std::{
+    collections::HashSet, error::Error, fmt, hash::Hash, iter::successors, str::FromStr,
+    time::Instant,
+};
+
 #[derive(Debug)]
-struct Game {
-    cups: Vec<usize>,
+struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid arguments: {}", self.0)
+    }
+}
+
+impl Error for ArgsError {}
+
+#[derive(Debug)]
+struct ParseGameError(&'static str);
+
+impl fmt::Display for ParseGameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Parse error ({})", self.0)
+    }
+}
+
+impl Error for ParseGameError {}
+
+trait CupIndex: Copy + fmt::Debug + Eq + Hash {
+    fn from_index(index: usize) -> Self;
+    fn index(self) -> usize;
+}
+
+impl CupIndex for usize {
+    fn from_index(index: usize) -> Self {
+        index
+    }
+
+    fn index(self) -> usize {
+        self
+    }
+}
+
+impl CupIndex for u32 {
+    fn from_index(index: usize) -> Self {
+        index as u32
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug)]
+struct CupGame<C> {
+    cups: Vec<C>,
     current: usize,
+    turns: usize,
+    pickup: usize,
+    picked: Vec<usize>,
 }
 
-impl Game {
-    pub fn new(start_pattern: &[usize]) -> Self {
-        assert!(start_pattern.len() > 5);
-        assert!((1..=start_pattern.len()).all(|i| start_pattern.contains(&i)));
-        let mut cups = vec![0; start_pattern.len()];
-        let mut cups_iterator = start_pattern.iter().map(|c| c - 1);
-        let current = cups_iterator.next().unwrap();
-        let mut prev = current;
-        for next in cups_iterator {
-            cups[prev] = next;
-            prev = next;
-        }
-        cups[prev] = current;
-        Self { cups, current }
+const DEFAULT_PICKUP: usize = 3;
+const MAX_SNAPSHOT_CUPS: usize = 100;
+
+type Game = CupGame<usize>;
+
+impl<C: CupIndex> CupGame<C> {
+    pub fn new(start_pattern: &[usize]) -> Result<Self, &'static str> {
+        Self::new_filled(start_pattern, start_pattern.len())
     }
 
-    pub fn new_million(start_pattern: &[usize]) -> Self {
-        let mut cups = (1..=1_000_000).collect::<Vec<_>>();
+    pub fn new_filled(start_pattern: &[usize], total: usize) -> Result<Self, &'static str> {
+        validate_pattern(start_pattern)?;
+        if total < start_pattern.len() {
+            return Err("total must be at least the number of cups in the start pattern");
+        }
+
+        let mut cups = (1..=total).map(C::from_index).collect::<Vec<_>>();
 
         let mut cups_iterator = start_pattern.iter().map(|c| c - 1);
         let current = cups_iterator.next().unwrap();
         let mut prev = current;
         for next in cups_iterator {
-            cups[prev] = next;
+            cups[prev] = C::from_index(next);
             prev = next;
         }
 
-        cups[prev] = start_pattern.len();
-        cups[999_999] = current;
+        if total > start_pattern.len() {
+            cups[prev] = C::from_index(start_pattern.len());
+            cups[total - 1] = C::from_index(current);
+        } else {
+            cups[prev] = C::from_index(current);
+        }
+
+        Ok(Self {
+            cups,
+            current,
+            turns: 0,
+            pickup: DEFAULT_PICKUP,
+            picked: Vec::with_capacity(DEFAULT_PICKUP),
+        })
+    }
 
-        Self { cups, current }
+    pub fn new_million(start_pattern: &[usize]) -> Result<Self, &'static str> {
+        Self::new_filled(start_pattern, 1_000_000)
+    }
+
+    // at least two cups must stay on the table: the current cup and the destination
+    pub fn with_pickup(mut self, pickup: usize) -> Result<Self, &'static str> {
+        if pickup == 0 || pickup >= self.cups.len() - 1 {
+            return Err("pickup must be at least 1 and leave at least 2 cups on the table");
+        }
+
+        self.pickup = pickup;
+        self.picked = Vec::with_capacity(pickup);
+        Ok(self)
     }
 
     pub fn play_turn(&mut self) {
-        let mut next3 = [self.cups[self.current]; 3];
-        let mut prev = next3[0];
-        for p in next3[1..].iter_mut() {
-            *p = self.cups[prev];
-            prev = *p;
+        self.picked.clear();
+        let mut prev = self.current;
+        for _ in 0..self.pickup {
+            prev = self.cups[prev].index();
+            self.picked.push(prev);
         }
 
         let mut next = self.current.checked_sub(1).unwrap_or(self.cups.len() - 1);
-        while next3.contains(&next) {
+        while self.picked.contains(&next) {
             next = next.checked_sub(1).unwrap_or(self.cups.len() - 1);
         }
 
-        self.cups[self.current] = self.cups[next3[2]];
-        self.cups[next3[2]] = self.cups[next];
-        self.cups[next] = next3[0];
-        self.current = self.cups[self.current];
+        let last_picked = self.picked[self.pickup - 1];
+        self.cups[self.current] = self.cups[last_picked];
+        self.cups[last_picked] = self.cups[next];
+        self.cups[next] = C::from_index(self.picked[0]);
+        self.current = self.cups[self.current].index();
+        self.turns += 1;
     }
 
-    pub fn labels_after_1(&self) -> u64 {
-        assert!(self.cups.len() < 10);
-        let mut result = 0;
-        let mut next = self.cups[0];
-        while next != 0 {
-            result = result * 10 + next as u64 + 1;
-            next = self.cups[next];
+    // records the cup order after each turn, keeping the current cup at the same position as
+    // the worked example in the puzzle description
+    pub fn play_with_snapshots(&mut self, turns: usize) -> Vec<Vec<usize>> {
+        assert!(self.cups.len() <= MAX_SNAPSHOT_CUPS);
+        let mut snapshots = Vec::with_capacity(turns);
+        for _ in 0..turns {
+            self.play_turn();
+            let mut snapshot = self
+                .cups_from(self.current + 1)
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            snapshot.rotate_right(self.turns % self.cups.len());
+            snapshots.push(snapshot);
+        }
+
+        snapshots
+    }
+
+    // returns the number of turns played when the game first reaches a configuration that it was
+    // in before, or None if it does not repeat within limit turns
+    pub fn detect_repeat(&mut self, limit: usize) -> Option<usize> {
+        let mut previous_states = HashSet::new();
+        previous_states.insert((self.cups.clone(), self.current));
+        for turn in 1..=limit {
+            self.play_turn();
+            if !previous_states.insert((self.cups.clone(), self.current)) {
+                return Some(turn);
+            }
         }
 
-        result
+        None
+    }
+
+    // returns None if there is no cup with the given label
+    pub fn cups_from(&self, label: usize) -> Option<impl Iterator<Item = usize> + '_> {
+        label
+            .checked_sub(1)
+            .filter(|&start| start < self.cups.len())
+            .map(move |start| {
+                successors(Some(start), move |&cup| {
+                    Some(self.cups[cup].index()).filter(|&next| next != start)
+                })
+                .map(|cup| cup + 1)
+            })
+    }
+
+    pub fn order_after_1(&self) -> Vec<usize> {
+        // every game contains cup 1, so the iterator is always present
+        self.cups_from(1).into_iter().flatten().skip(1).collect()
+    }
+
+    pub fn labels_after_1(&self) -> u64 {
+        assert!(self.cups.len() < 10);
+        labels_to_number(&self.order_after_1())
     }
 
     pub fn score_after_1(&self) -> u64 {
-        let first = self.cups[0] as u64 + 1;
-        let second = self.cups[self.cups[0]] as u64 + 1;
-        first * second
+        self.cups_from(1)
+            .into_iter()
+            .flatten()
+            .skip(1)
+            .take(2)
+            .map(|cup| cup as u64)
+            .product()
+    }
+}
+
+fn validate_pattern(pattern: &[usize]) -> Result<(), &'static str> {
+    if pattern.len() <= 5 {
+        return Err("start pattern must contain at least 6 cups");
+    }
+
+    if !(1..=pattern.len()).all(|i| pattern.contains(&i)) {
+        return Err("start pattern must be a permutation of 1 to n");
+    }
+
+    Ok(())
+}
+
+fn parse_pattern(s: &str) -> Result<Vec<usize>, ParseGameError> {
+    s.chars()
+        .map(|c| c.to_digit(10).map(|d| d as usize))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(ParseGameError("cups must be digits"))
+}
+
+impl<C: CupIndex> FromStr for CupGame<C> {
+    type Err = ParseGameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(&parse_pattern(s)?).map_err(ParseGameError)
     }
 }
 
+fn labels_to_number(labels: &[usize]) -> u64 {
+    labels
+        .iter()
+        .fold(0, |result, &label| result * 10 + label as u64)
+}
+
 const INPUT: [usize; 9] = [9, 4, 2, 3, 8, 7, 6, 1, 5];
+const ROUNDS: usize = 100;
+const PART2_ROUNDS: usize = 10_000_000;
 
-fn main() {
-    let part1 = {
-        let mut game = Game::new(&INPUT);
-        for _ in 0..100 {
-            game.play_turn();
+#[derive(Debug)]
+struct Options {
+    start_pattern: Vec<usize>,
+    rounds: usize,
+    part2_rounds: usize,
+    snapshots: bool,
+    benchmark: bool,
+    repeat_limit: Option<usize>,
+    pickup: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            start_pattern: INPUT.to_vec(),
+            rounds: ROUNDS,
+            part2_rounds: PART2_ROUNDS,
+            snapshots: false,
+            benchmark: false,
+            repeat_limit: None,
+            pickup: DEFAULT_PICKUP,
+        }
+    }
+}
+
+fn parse_start_pattern(s: &str) -> Result<Vec<usize>, ArgsError> {
+    let start_pattern =
+        parse_pattern(s).map_err(|e| ArgsError(format!("starting cups {:?}: {}", s, e)))?;
+    validate_pattern(&start_pattern)
+        .map_err(|e| ArgsError(format!("starting cups {:?}: {}", s, e)))?;
+    Ok(start_pattern)
+}
+
+fn parse_rounds(value: Option<String>) -> Result<usize, ArgsError> {
+    let value = value.ok_or_else(|| ArgsError("missing round count".to_string()))?;
+    value
+        .parse()
+        .map_err(|_| ArgsError(format!("invalid round count {:?}", value)))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, ArgsError> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rounds" => options.rounds = parse_rounds(args.next())?,
+            "--part2-rounds" => options.part2_rounds = parse_rounds(args.next())?,
+            "--snapshots" => options.snapshots = true,
+            "--benchmark" => options.benchmark = true,
+            "--detect-repeat" => options.repeat_limit = Some(parse_rounds(args.next())?),
+            "--pickup" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| ArgsError("missing pickup count".to_string()))?;
+                options.pickup = value
+                    .parse()
+                    .map_err(|_| ArgsError(format!("invalid pickup count {:?}", value)))?;
+            }
+            _ if arg.starts_with("--") => {
+                return Err(ArgsError(format!("unknown option {:?}", arg)))
+            }
+            _ => options.start_pattern = parse_start_pattern(&arg)?,
         }
-        game.labels_after_1()
-    };
+    }
+
+    Ok(options)
+}
 
+fn part1(start_pattern: &[usize], rounds: usize, pickup: usize) -> Result<u64, Box<dyn Error>> {
+    let mut game = Game::new(start_pattern)?.with_pickup(pickup)?;
+    for _ in 0..rounds {
+        game.play_turn();
+    }
+    Ok(game.labels_after_1())
+}
+
+fn part2<C: CupIndex>(
+    start_pattern: &[usize],
+    rounds: usize,
+    pickup: usize,
+) -> Result<u64, Box<dyn Error>> {
+    let mut game = CupGame::<C>::new_million(start_pattern)?.with_pickup(pickup)?;
+    for _ in 0..rounds {
+        game.play_turn();
+    }
+    Ok(game.score_after_1())
+}
+
+fn benchmark<C: CupIndex>(
+    name: &str,
+    start_pattern: &[usize],
+    rounds: usize,
+    pickup: usize,
+) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let result = part2::<C>(start_pattern, rounds, pickup)?;
+    println!(
+        "Benchmark: {} cups, result = {}, elapsed = {:?}",
+        name,
+        result,
+        start.elapsed()
+    );
+    Ok(())
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let options = parse_args(std::env::args().skip(1))?;
+
+    if options.snapshots {
+        let mut game = Game::new(&options.start_pattern)?.with_pickup(options.pickup)?;
+        for (turn, snapshot) in game.play_with_snapshots(options.rounds).iter().enumerate() {
+            let cups = snapshot.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            println!("-- move {} -- cups: {}", turn + 1, cups.join(" "));
+        }
+    }
+
+    let part1 = part1(&options.start_pattern, options.rounds, options.pickup)?;
     println!("Part 1: result = {}", part1);
 
-    let part2 = {
-        let mut game = Game::new_million(&INPUT);
-        for _ in 0..10_000_000 {
-            game.play_turn();
+    if let Some(limit) = options.repeat_limit {
+        let mut game = Game::new(&options.start_pattern)?.with_pickup(options.pickup)?;
+        match game.detect_repeat(limit) {
+            Some(turn) => println!("Part 1: configuration repeats after {} turns", turn),
+            None => println!("Part 1: no repeat within {} turns", limit),
         }
-        game.score_after_1()
-    };
+    }
 
+    let part2 = part2::<usize>(&options.start_pattern, options.part2_rounds, options.pickup)?;
     println!("Part 2: result = {}", part2);
+
+    if options.benchmark {
+        let (pattern, rounds, pickup) =
+            (&options.start_pattern, options.part2_rounds, options.pickup);
+        benchmark::<usize>("usize", pattern, rounds, pickup)?;
+        benchmark::<u32>("u32", pattern, rounds, pickup)?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    std::process::exit(match run() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error occurred: {}", e);
+            1
+        }
+    });
 }
 
 #[cfg(test)]
 mod test {
-    use super::Game;
+    use super::{parse_args, part1, CupGame, Game};
 
     const TEST_INPUT: [usize; 9] = [3, 8, 9, 1, 2, 5, 4, 6, 7];
 
     #[test]
     fn example_game_10() {
-        let mut game = Game::new(&TEST_INPUT);
+        let mut game = Game::new(&TEST_INPUT).unwrap();
         for _ in 0..10 {
             game.play_turn();
         }
@@ -117,7 +409,7 @@ mod test {
 
     #[test]
     fn example_game_100() {
-        let mut game = Game::new(&TEST_INPUT);
+        let mut game = Game::new(&TEST_INPUT).unwrap();
         for _ in 0..100 {
             game.play_turn();
         }
@@ -125,9 +417,163 @@ mod test {
         assert_eq!(result, 67384529);
     }
 
+    #[test]
+    fn example_game_order() {
+        let mut game = Game::new(&TEST_INPUT).unwrap();
+        for _ in 0..10 {
+            game.play_turn();
+        }
+        assert_eq!(game.order_after_1(), vec![9, 2, 6, 5, 8, 3, 7, 4]);
+    }
+
+    #[test]
+    fn invalid_start_patterns() {
+        let result = Game::new(&[3, 1, 2, 5, 4]);
+        assert_eq!(
+            result.unwrap_err(),
+            "start pattern must contain at least 6 cups"
+        );
+        let result = Game::new(&[3, 1, 2, 7, 5, 4]);
+        assert_eq!(
+            result.unwrap_err(),
+            "start pattern must be a permutation of 1 to n"
+        );
+    }
+
+    #[test]
+    fn parse_game() {
+        let game = "389125467".parse::<Game>().unwrap();
+        let expected = Game::new(&TEST_INPUT).unwrap();
+        assert_eq!(game.cups, expected.cups);
+        assert_eq!(game.current, expected.current);
+
+        assert!("38912546".parse::<Game>().is_err());
+        assert!("3891254x7".parse::<Game>().is_err());
+    }
+
+    #[test]
+    fn example_cups_from() {
+        let game = Game::new(&TEST_INPUT).unwrap();
+        let cups = game.cups_from(1).unwrap().collect::<Vec<_>>();
+        assert_eq!(cups, vec![1, 2, 5, 4, 6, 7, 3, 8, 9]);
+        let cups = game.cups_from(3).unwrap().collect::<Vec<_>>();
+        assert_eq!(cups, TEST_INPUT);
+        assert!(game.cups_from(0).is_none());
+        assert!(game.cups_from(10).is_none());
+    }
+
+    #[test]
+    fn example_snapshots() {
+        let mut game = Game::new(&TEST_INPUT).unwrap();
+        let snapshots = game.play_with_snapshots(10);
+        assert_eq!(snapshots.len(), 10);
+        assert_eq!(snapshots[0], vec![3, 2, 8, 9, 1, 5, 4, 6, 7]);
+        assert_eq!(snapshots[1], vec![3, 2, 5, 4, 6, 7, 8, 9, 1]);
+        assert_eq!(snapshots[2], vec![7, 2, 5, 8, 9, 1, 3, 4, 6]);
+        assert_eq!(snapshots[3], vec![3, 2, 5, 8, 4, 6, 7, 9, 1]);
+        assert_eq!(snapshots[9], vec![5, 8, 3, 7, 4, 1, 9, 2, 6]);
+    }
+
+    #[test]
+    fn detect_repeat() {
+        let mut game = Game::new(&[3, 1, 2, 6, 5, 4]).unwrap();
+        assert_eq!(game.detect_repeat(100), Some(3));
+        assert_eq!(
+            game.cups_from(3).unwrap().collect::<Vec<_>>(),
+            vec![3, 1, 2, 6, 5, 4]
+        );
+
+        let mut game = Game::new(&[1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(game.detect_repeat(3), None);
+        let mut game = Game::new(&[1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(game.detect_repeat(100), Some(4));
+    }
+
+    #[test]
+    fn example_game_pickup_2() {
+        let mut game = Game::new(&TEST_INPUT).unwrap().with_pickup(2).unwrap();
+        game.play_turn();
+        assert_eq!(game.labels_after_1(), 28954673);
+        game.play_turn();
+        assert_eq!(game.labels_after_1(), 92854673);
+        for _ in 2..10 {
+            game.play_turn();
+        }
+        assert_eq!(game.labels_after_1(), 47953286);
+
+        assert!(Game::new(&TEST_INPUT).unwrap().with_pickup(0).is_err());
+        assert!(Game::new(&TEST_INPUT).unwrap().with_pickup(7).is_ok());
+        assert!(Game::new(&TEST_INPUT).unwrap().with_pickup(8).is_err());
+    }
+
+    #[test]
+    fn custom_arguments() {
+        let args = ["389125467", "--rounds", "10"];
+        let options = parse_args(args.iter().map(|s| s.to_string())).unwrap();
+        assert_eq!(options.start_pattern, TEST_INPUT);
+        assert_eq!(options.rounds, 10);
+        assert_eq!(
+            part1(&options.start_pattern, options.rounds, options.pickup).unwrap(),
+            92658374
+        );
+
+        let args = ["389125467", "--pickup", "2"];
+        let options = parse_args(args.iter().map(|s| s.to_string())).unwrap();
+        assert_eq!(options.pickup, 2);
+        assert_eq!(
+            part1(&options.start_pattern, 10, options.pickup).unwrap(),
+            47953286
+        );
+
+        let args = ["389125467", "--pickup", "9"];
+        let options = parse_args(args.iter().map(|s| s.to_string())).unwrap();
+        assert!(part1(&options.start_pattern, 10, options.pickup).is_err());
+        assert!(parse_args(["--pickup"].iter().map(|s| s.to_string())).is_err());
+        assert!(parse_args(["--pickup", "x"].iter().map(|s| s.to_string())).is_err());
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        for args in [
+            &["389125466"][..],
+            &["38912546a"],
+            &["3891"],
+            &["--rounds"],
+            &["--rounds", "ten"],
+            &["--speed", "10"],
+        ]
+        .iter()
+        {
+            assert!(parse_args(args.iter().map(|s| s.to_string())).is_err());
+        }
+    }
+
+    #[test]
+    fn example_game_filled_20() {
+        let mut game = Game::new_filled(&TEST_INPUT, 20).unwrap();
+        for _ in 0..100 {
+            game.play_turn();
+        }
+        assert_eq!(game.cups_from(1).unwrap().nth(1), Some(9));
+        assert_eq!(game.cups_from(1).unwrap().nth(2), Some(6));
+        assert_eq!(game.score_after_1(), 54);
+
+        assert!(Game::new_filled(&TEST_INPUT, 8).is_err());
+    }
+
     #[test]
     fn example_game_10_000_000() {
-        let mut game = Game::new_million(&TEST_INPUT);
+        let mut game = Game::new_million(&TEST_INPUT).unwrap();
+        for _ in 0..10_000_000 {
+            game.play_turn();
+        }
+        let result = game.score_after_1();
+        assert_eq!(result, 149245887792);
+    }
+
+    #[test]
+    fn example_game_10_000_000_u32() {
+        let mut game = CupGame::<u32>::new_million(&TEST_INPUT).unwrap();
         for _ in 0..10_000_000 {
             game.play_turn();
         }
@@ -0,0 +1,121 @@
+use crate::{Coords, Floor, DEFAULT_BIRTH, DEFAULT_SURVIVE};
+
+const GROWTH: i32 = 8;
+
+// Stores the floor as a grid indexed by axial coordinates relative to origin, keeping the two
+// outermost rows and columns white so that the update sweep never needs to look past the edges.
+pub struct DenseFloor {
+    tiles: Vec<bool>,
+    scratch: Vec<bool>,
+    origin: Coords,
+    width: i32,
+    height: i32,
+}
+
+impl DenseFloor {
+    pub fn new(floor: &Floor) -> Self {
+        let mut dense = Self {
+            tiles: Vec::new(),
+            scratch: Vec::new(),
+            origin: Coords(0, 0),
+            width: 0,
+            height: 0,
+        };
+
+        if let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = (
+            floor.black_tiles.iter().map(|c| c.0).min(),
+            floor.black_tiles.iter().map(|c| c.0).max(),
+            floor.black_tiles.iter().map(|c| c.1).min(),
+            floor.black_tiles.iter().map(|c| c.1).max(),
+        ) {
+            dense.resize(
+                Coords(min_x - GROWTH, min_y - GROWTH),
+                Coords(max_x + GROWTH, max_y + GROWTH),
+            );
+            for coords in &floor.black_tiles {
+                let index = dense.index(*coords);
+                dense.tiles[index] = true;
+            }
+        }
+
+        dense
+    }
+
+    fn index(&self, coords: Coords) -> usize {
+        ((coords.1 - self.origin.1) * self.width + coords.0 - self.origin.0) as usize
+    }
+
+    fn resize(&mut self, min: Coords, max: Coords) {
+        let width = max.0 - min.0 + 1;
+        let height = max.1 - min.1 + 1;
+        let mut tiles = vec![false; (width * height) as usize];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.tiles[(y * self.width + x) as usize] {
+                    let coords = Coords(self.origin.0 + x, self.origin.1 + y);
+                    tiles[((coords.1 - min.1) * width + coords.0 - min.0) as usize] = true;
+                }
+            }
+        }
+
+        self.tiles = tiles;
+        self.scratch = vec![false; (width * height) as usize];
+        self.origin = min;
+        self.width = width;
+        self.height = height;
+    }
+
+    fn needs_growth(&self) -> bool {
+        (0..self.height).any(|y| {
+            let row = &self.tiles[(y * self.width) as usize..((y + 1) * self.width) as usize];
+            if y < 2 || y >= self.height - 2 {
+                row.contains(&true)
+            } else {
+                row[..2].contains(&true) || row[(self.width - 2) as usize..].contains(&true)
+            }
+        })
+    }
+
+    pub fn count_black_tiles(&self) -> usize {
+        self.tiles.iter().filter(|&&t| t).count()
+    }
+
+    pub fn update(&mut self) {
+        if self.tiles.is_empty() {
+            return;
+        }
+
+        if self.needs_growth() {
+            self.resize(
+                Coords(self.origin.0 - GROWTH, self.origin.1 - GROWTH),
+                Coords(
+                    self.origin.0 + self.width - 1 + GROWTH,
+                    self.origin.1 + self.height - 1 + GROWTH,
+                ),
+            );
+        }
+
+        let width = self.width as usize;
+        let offsets = [1, width + 1, width];
+        for y in 1..self.height as usize - 1 {
+            for x in 1..width - 1 {
+                let index = y * width + x;
+                let neighbor_count = offsets
+                    .iter()
+                    .filter(|&&offset| self.tiles[index + offset])
+                    .count()
+                    + offsets
+                        .iter()
+                        .filter(|&&offset| self.tiles[index - offset])
+                        .count();
+                self.scratch[index] = if self.tiles[index] {
+                    DEFAULT_SURVIVE.contains(&neighbor_count)
+                } else {
+                    DEFAULT_BIRTH.contains(&neighbor_count)
+                };
+            }
+        }
+
+        std::mem::swap(&mut self.tiles, &mut self.scratch);
+    }
+}
@@ -3,81 +3,183 @@ use std::{
     fmt,
     fs::File,
     io::{BufRead, BufReader},
+    ops::Add,
     path::PathBuf,
+    time::Instant,
 };
 
 use ahash::AHashSet;
 
-#[derive(Debug)]
-struct ParseCoordsError(&'static str);
+mod dense;
+use dense::DenseFloor;
+
+#[derive(Debug, PartialEq)]
+enum ParseCoordsError {
+    InvalidCharacter(usize, char),
+    IncompleteDirection(usize, char),
+}
 
 impl fmt::Display for ParseCoordsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Parse error: {}", self.0)
+        match self {
+            Self::InvalidCharacter(i, c) => {
+                write!(f, "Parse error: invalid character {:?} at index {}", c, i)
+            }
+            Self::IncompleteDirection(i, c) => write!(
+                f,
+                "Parse error: incomplete direction {:?} at index {} at end of line",
+                c, i
+            ),
+        }
     }
 }
 
 impl Error for ParseCoordsError {}
 
+#[derive(Debug)]
+struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid arguments: {}", self.0)
+    }
+}
+
+impl Error for ArgsError {}
+
+#[derive(Debug, PartialEq)]
+struct RuleError;
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid rule: birth cannot require zero black neighbors")
+    }
+}
+
+impl Error for RuleError {}
+
+// Axial coordinates: the first component increases towards the east, the second towards the
+// south-west, so that north-west and south-east change both components, while north-east and
+// south-west only change the second one.
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 struct Coords(i32, i32);
 
+impl Add for Coords {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum Direction {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast,
+}
+
+// offsets indexed by Direction, in clockwise order starting from east
+const DIRECTIONS: [Coords; 6] = [
+    Coords(1, 0),
+    Coords(1, 1),
+    Coords(0, 1),
+    Coords(-1, 0),
+    Coords(-1, -1),
+    Coords(0, -1),
+];
+
+impl Direction {
+    pub const ALL: [Self; 6] = [
+        Self::East,
+        Self::SouthEast,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+        Self::NorthEast,
+    ];
+
+    pub fn offset(self) -> Coords {
+        DIRECTIONS[self as usize]
+    }
+
+    #[cfg(test)]
+    pub fn opposite(self) -> Self {
+        Self::ALL[(self as usize + 3) % 6]
+    }
+}
+
 impl Coords {
     pub fn parse_line(line: &str) -> Result<Self, ParseCoordsError> {
         enum ParseState {
             None,
-            North,
-            South,
+            North(usize),
+            South(usize),
         }
 
         let mut state = ParseState::None;
         let mut x = 0;
         let mut y = 0;
 
-        for c in line.bytes() {
+        for (i, c) in line.char_indices() {
             match (&state, c) {
-                (ParseState::None, b'w') => x -= 1,
-                (ParseState::None, b'e') => x += 1,
-                (ParseState::None, b'n') => {
+                (ParseState::None, 'w') => x -= 1,
+                (ParseState::None, 'e') => x += 1,
+                (ParseState::None, 'n') => {
                     y -= 1;
-                    state = ParseState::North;
+                    state = ParseState::North(i);
                 }
-                (ParseState::None, b's') => {
+                (ParseState::None, 's') => {
                     y += 1;
-                    state = ParseState::South;
+                    state = ParseState::South(i);
                 }
-                (ParseState::North, b'w') => {
+                (ParseState::North(_), 'w') => {
                     x -= 1;
                     state = ParseState::None;
                 }
-                (ParseState::North, b'e') => state = ParseState::None,
-                (ParseState::South, b'w') => state = ParseState::None,
-                (ParseState::South, b'e') => {
+                (ParseState::North(_), 'e') => state = ParseState::None,
+                (ParseState::South(_), 'w') => state = ParseState::None,
+                (ParseState::South(_), 'e') => {
                     x += 1;
                     state = ParseState::None;
                 }
-                _ => return Err(ParseCoordsError("Invalid character")),
+                _ => return Err(ParseCoordsError::InvalidCharacter(i, c)),
             }
         }
 
         match state {
             ParseState::None => Ok(Coords(x, y)),
-            _ => Err(ParseCoordsError("Unexpected end of line")),
+            ParseState::North(i) => Err(ParseCoordsError::IncompleteDirection(i, 'n')),
+            ParseState::South(i) => Err(ParseCoordsError::IncompleteDirection(i, 's')),
         }
     }
 
+    pub fn neighbor(&self, direction: Direction) -> Self {
+        *self + direction.offset()
+    }
+
+    pub fn distance(&self, other: &Self) -> i32 {
+        let dx = other.0 - self.0;
+        let dy = other.1 - self.1;
+        (dx.abs() + dy.abs() + (dx - dy).abs()) / 2
+    }
+
     pub fn get_neighbors(&self) -> [Self; 6] {
-        [
-            Self(self.0 - 1, self.1),
-            Self(self.0 + 1, self.1),
-            Self(self.0 - 1, self.1 - 1),
-            Self(self.0, self.1 - 1),
-            Self(self.0, self.1 + 1),
-            Self(self.0 + 1, self.1 + 1),
-        ]
+        let mut neighbors = [*self; 6];
+        for (neighbor, direction) in neighbors.iter_mut().zip(Direction::ALL.iter()) {
+            *neighbor = self.neighbor(*direction);
+        }
+        neighbors
     }
 }
 
+const DEFAULT_SURVIVE: [usize; 2] = [1, 2];
+const DEFAULT_BIRTH: [usize; 1] = [2];
+
+#[derive(Clone)]
 struct Floor {
     black_tiles: AHashSet<Coords>,
 }
@@ -99,11 +201,80 @@ impl Floor {
         Ok(Self { black_tiles })
     }
 
+    pub fn is_black(&self, coords: Coords) -> bool {
+        self.black_tiles.contains(&coords)
+    }
+
+    pub fn count_black_within(&self, center: Coords, radius: i32) -> usize {
+        self.black_tiles
+            .iter()
+            .filter(|coords| center.distance(coords) <= radius)
+            .count()
+    }
+
     pub fn count_black_tiles(&self) -> usize {
         self.black_tiles.len()
     }
 
     pub fn update(&mut self) {
+        self.apply_rule(&DEFAULT_SURVIVE, &DEFAULT_BIRTH);
+    }
+
+    // rows follow the second axial component, with each row shifted half a tile relative to the
+    // previous one so that the tiles line up as they would on the hexagonal floor
+    pub fn render(&self) -> String {
+        let column = |c: &Coords| 2 * c.0 - c.1;
+        let (min_row, max_row, min_column, max_column) = match (
+            self.black_tiles.iter().map(|c| c.1).min(),
+            self.black_tiles.iter().map(|c| c.1).max(),
+            self.black_tiles.iter().map(column).min(),
+            self.black_tiles.iter().map(column).max(),
+        ) {
+            (Some(min_row), Some(max_row), Some(min_column), Some(max_column)) => {
+                (min_row, max_row, min_column, max_column)
+            }
+            _ => return String::new(),
+        };
+
+        let mut result = String::new();
+        for row in min_row..=max_row {
+            let mut line = String::new();
+            for column in min_column..=max_column {
+                if (row + column) % 2 != 0 {
+                    line.push(' ');
+                } else if self.is_black(Coords((row + column) / 2, row)) {
+                    line.push('#');
+                } else {
+                    line.push('.');
+                }
+            }
+            result.push_str(line.trim_end());
+            result.push('\n');
+        }
+
+        result
+    }
+
+    pub fn simulate(&mut self, days: usize) -> Vec<usize> {
+        (0..days)
+            .map(|_| {
+                self.update();
+                self.count_black_tiles()
+            })
+            .collect()
+    }
+
+    // only white tiles adjacent to a black tile are considered, so birth cannot include zero
+    pub fn update_with(&mut self, survive: &[usize], birth: &[usize]) -> Result<(), RuleError> {
+        if birth.contains(&0) {
+            return Err(RuleError);
+        }
+
+        self.apply_rule(survive, birth);
+        Ok(())
+    }
+
+    fn apply_rule(&mut self, survive: &[usize], birth: &[usize]) {
         let mut new_tiles = AHashSet::with_capacity(self.count_black_tiles() * 2);
         let mut white_tile_check = AHashSet::with_capacity(self.count_black_tiles() * 6);
 
@@ -117,19 +288,18 @@ impl Floor {
                 }
             }
 
-            if matches!(neighbor_count, 1 | 2) {
+            if survive.contains(&neighbor_count) {
                 new_tiles.insert(*coordinates);
             }
         }
 
         for coordinates in white_tile_check {
-            if coordinates
+            let neighbor_count = coordinates
                 .get_neighbors()
                 .iter()
                 .filter(|n| self.black_tiles.contains(n))
-                .count()
-                == 2
-            {
+                .count();
+            if birth.contains(&neighbor_count) {
                 new_tiles.insert(coordinates);
             }
         }
@@ -138,18 +308,155 @@ impl Floor {
     }
 }
 
+const DAYS: usize = 100;
+
+#[derive(Debug)]
+struct Options {
+    days: usize,
+    radius: Option<i32>,
+    rule: Option<(Vec<usize>, Vec<usize>)>,
+    render: bool,
+    benchmark: bool,
+}
+
+// rules are written as survive/birth neighbor counts, e.g. 12/2 for the puzzle's rule
+fn parse_rule(s: &str) -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut parts = s.split('/').map(|part| {
+        part.chars()
+            .map(|c| c.to_digit(10).filter(|&n| n <= 6).map(|n| n as usize))
+            .collect::<Option<Vec<_>>>()
+    });
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(survive), Some(birth), None) => Some((survive?, birth?)),
+        _ => None,
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, ArgsError> {
+    let mut options = Options {
+        days: DAYS,
+        radius: None,
+        rule: None,
+        render: false,
+        benchmark: false,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--days" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| ArgsError("missing number of days".to_string()))?;
+                options.days = value
+                    .parse()
+                    .map_err(|_| ArgsError(format!("invalid number of days {:?}", value)))?;
+            }
+            "--radius" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| ArgsError("missing radius".to_string()))?;
+                let radius = value
+                    .parse()
+                    .ok()
+                    .filter(|&r| r >= 0)
+                    .ok_or_else(|| ArgsError(format!("invalid radius {:?}", value)))?;
+                options.radius = Some(radius);
+            }
+            "--rule" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| ArgsError("missing rule".to_string()))?;
+                let rule = parse_rule(&value)
+                    .ok_or_else(|| ArgsError(format!("invalid rule {:?}", value)))?;
+                options.rule = Some(rule);
+            }
+            "--render" => options.render = true,
+            "--benchmark" => options.benchmark = true,
+            _ => return Err(ArgsError(format!("unknown argument {:?}", arg))),
+        }
+    }
+
+    Ok(options)
+}
+
+fn part2(floor: &mut Floor, days: usize) -> usize {
+    floor.simulate(days);
+    floor.count_black_tiles()
+}
+
+fn part2_with(
+    floor: &mut Floor,
+    days: usize,
+    survive: &[usize],
+    birth: &[usize],
+) -> Result<usize, RuleError> {
+    for _ in 0..days {
+        floor.update_with(survive, birth)?;
+    }
+    Ok(floor.count_black_tiles())
+}
+
+fn benchmark(floor: &Floor, days: usize) {
+    let start = Instant::now();
+    let mut sparse = floor.clone();
+    let count = part2(&mut sparse, days);
+    println!(
+        "Benchmark: sparse floor, result = {}, elapsed = {:?}",
+        count,
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let mut dense = DenseFloor::new(floor);
+    for _ in 0..days {
+        dense.update();
+    }
+    println!(
+        "Benchmark: dense floor, result = {}, elapsed = {:?}",
+        dense.count_black_tiles(),
+        start.elapsed()
+    );
+}
+
 fn run() -> Result<(), Box<dyn Error>> {
+    let options = parse_args(std::env::args().skip(1))?;
     let mut floor = {
         let path = ["data", "day24", "input.txt"].iter().collect::<PathBuf>();
         let file = File::open(path)?;
-        Floor::parse(BufReader::new(file).lines().filter_map(Result::ok))?
+        Floor::parse(BufReader::new(file).lines().map_while(Result::ok))?
     };
 
     println!("Part 1: result = {}", floor.count_black_tiles());
-    for _ in 0..100 {
-        floor.update();
+    if let Some(radius) = options.radius {
+        println!(
+            "Part 1: {} black tiles within {} of the reference tile",
+            floor.count_black_within(Coords(0, 0), radius),
+            radius
+        );
+    }
+
+    if options.benchmark {
+        benchmark(&floor, options.days);
+    }
+    // rule variants start from the same initial floor as part 2
+    let variant = options.rule.as_ref().map(|rule| (rule, floor.clone()));
+    println!(
+        "Part 2: result after {} days = {}",
+        options.days,
+        part2(&mut floor, options.days)
+    );
+    if let Some(((survive, birth), mut variant)) = variant {
+        println!(
+            "Part 2: result after {} days with rule {:?}/{:?} = {}",
+            options.days,
+            survive,
+            birth,
+            part2_with(&mut variant, options.days, survive, birth)?
+        );
+    }
+
+    if options.render {
+        print!("{}", floor.render());
     }
-    println!("Part 2: result = {}", floor.count_black_tiles());
 
     Ok(())
 }
@@ -166,7 +473,10 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::Floor;
+    use super::{
+        parse_args, part2, part2_with, Coords, DenseFloor, Direction, Floor, ParseCoordsError,
+        RuleError, DEFAULT_BIRTH, DEFAULT_SURVIVE, DIRECTIONS,
+    };
 
     const TEST_INPUT: &str = r"sesenwnenenewseeswwswswwnenewsewsw
 neeenesenwnwwswnenewnwwsewnenwseswesw
@@ -189,6 +499,89 @@ eneswnwswnwsenenwnwnwwseeswneewsenese
 neswnwewnwnwseenwseesewsenwsweewe
 wseweeenwnesenwwwswnew";
 
+    #[test]
+    fn direction_test() {
+        let origin = Coords(0, 0);
+        for direction in Direction::ALL.iter() {
+            let opposite = direction.opposite();
+            assert_ne!(*direction, opposite);
+            assert_eq!(opposite.opposite(), *direction);
+            assert_eq!(origin.neighbor(*direction), DIRECTIONS[*direction as usize]);
+            assert_eq!(origin.neighbor(*direction).neighbor(opposite), origin);
+        }
+
+        assert_eq!(Coords::parse_line("e").unwrap(), Direction::East.offset());
+        assert_eq!(
+            Coords::parse_line("se").unwrap(),
+            Direction::SouthEast.offset()
+        );
+        assert_eq!(
+            Coords::parse_line("sw").unwrap(),
+            Direction::SouthWest.offset()
+        );
+        assert_eq!(Coords::parse_line("w").unwrap(), Direction::West.offset());
+        assert_eq!(
+            Coords::parse_line("nw").unwrap(),
+            Direction::NorthWest.offset()
+        );
+        assert_eq!(
+            Coords::parse_line("ne").unwrap(),
+            Direction::NorthEast.offset()
+        );
+    }
+
+    #[test]
+    fn add_test() {
+        assert_eq!(Coords(1, -2) + Coords(3, 4), Coords(4, 2));
+        let coords = DIRECTIONS
+            .iter()
+            .fold(Coords(0, 0), |acc, &offset| acc + offset);
+        assert_eq!(coords, Coords(0, 0));
+    }
+
+    #[test]
+    fn distance_test() {
+        let origin = Coords(0, 0);
+        const DISTANCES: [(&str, i32); 7] = [
+            ("nwwswee", 0),
+            ("e", 1),
+            ("esew", 1),
+            ("esenee", 3),
+            ("nenw", 2),
+            ("ene", 2),
+            ("wwswsenenw", 2),
+        ];
+        for (line, expected) in DISTANCES.iter() {
+            let coords = Coords::parse_line(line).unwrap();
+            assert_eq!(origin.distance(&coords), *expected);
+            assert_eq!(coords.distance(&origin), *expected);
+        }
+
+        for direction in Direction::ALL.iter() {
+            assert_eq!(origin.distance(&direction.offset()), 1);
+        }
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let error = Coords::parse_line("nenw?").unwrap_err();
+        assert_eq!(error, ParseCoordsError::InvalidCharacter(4, '?'));
+        assert_eq!(
+            error.to_string(),
+            "Parse error: invalid character '?' at index 4"
+        );
+
+        let error = Coords::parse_line("nen").unwrap_err();
+        assert_eq!(error, ParseCoordsError::IncompleteDirection(2, 'n'));
+        assert_eq!(
+            error.to_string(),
+            "Parse error: incomplete direction 'n' at index 2 at end of line"
+        );
+
+        let error = Coords::parse_line("nx").unwrap_err();
+        assert_eq!(error, ParseCoordsError::InvalidCharacter(1, 'x'));
+    }
+
     #[test]
     fn part1_test() {
         let floor = Floor::parse(TEST_INPUT.lines()).unwrap();
@@ -227,4 +620,135 @@ wseweeenwnesenwwwswnew";
             }
         }
     }
+
+    #[test]
+    fn is_black_test() {
+        let floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        let mut lines = TEST_INPUT.lines();
+        let flipped_once = Coords::parse_line(lines.next().unwrap()).unwrap();
+        assert!(floor.is_black(flipped_once));
+        let flipped_twice = Coords::parse_line(lines.next().unwrap()).unwrap();
+        assert!(!floor.is_black(flipped_twice));
+        assert!(!floor.is_black(Coords(10, 10)));
+    }
+
+    #[test]
+    fn count_black_within_test() {
+        let floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        let origin = Coords(0, 0);
+        assert_eq!(floor.count_black_within(origin, 0), 1);
+        assert_eq!(floor.count_black_within(origin, 1), 2);
+        assert_eq!(floor.count_black_within(origin, 2), 7);
+        assert_eq!(floor.count_black_within(origin, 3), 10);
+        assert_eq!(floor.count_black_within(Coords(2, 1), 1), 2);
+    }
+
+    #[test]
+    fn render_test() {
+        let floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        let rendered = floor.render();
+        assert_eq!(rendered.chars().filter(|&c| c == '#').count(), 10);
+        assert!(rendered
+            .chars()
+            .all(|c| matches!(c, '#' | '.' | ' ' | '\n')));
+
+        let floor = Floor::parse(["e", "se", "w"].iter()).unwrap();
+        assert_eq!(floor.render(), "# . #\n . #\n");
+    }
+
+    #[test]
+    fn simulate_test() {
+        let mut floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        let counts = floor.simulate(100);
+        assert_eq!(counts.len(), 100);
+        for (day, expected) in EXAMPLE_TILES.iter() {
+            assert_eq!(counts[day - 1], *expected);
+        }
+    }
+
+    #[test]
+    fn dense_test() {
+        let mut floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        let mut dense = DenseFloor::new(&floor);
+        assert_eq!(dense.count_black_tiles(), 10);
+        for _ in 0..100 {
+            floor.update();
+            dense.update();
+            assert_eq!(dense.count_black_tiles(), floor.count_black_tiles());
+        }
+        assert_eq!(dense.count_black_tiles(), 2208);
+
+        let mut dense = DenseFloor::new(&Floor::parse(std::iter::empty::<&str>()).unwrap());
+        dense.update();
+        assert_eq!(dense.count_black_tiles(), 0);
+    }
+
+    #[test]
+    fn update_with_test() {
+        let mut floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        assert_eq!(floor.count_black_tiles(), 10);
+        floor.update_with(&DEFAULT_SURVIVE, &DEFAULT_BIRTH).unwrap();
+        assert_eq!(floor.count_black_tiles(), 15);
+        for _ in 1..100 {
+            floor.update_with(&DEFAULT_SURVIVE, &DEFAULT_BIRTH).unwrap();
+        }
+        assert_eq!(floor.count_black_tiles(), 2208);
+
+        let mut floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        floor.update_with(&[], &[]).unwrap();
+        assert_eq!(floor.count_black_tiles(), 0);
+
+        assert_eq!(floor.update_with(&[1], &[0, 2]), Err(RuleError));
+    }
+
+    #[test]
+    fn days_argument_test() {
+        let args = ["--days", "10"];
+        let options = parse_args(args.iter().map(|s| s.to_string())).unwrap();
+        assert_eq!(options.days, 10);
+        let mut floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        assert_eq!(part2(&mut floor, options.days), 37);
+
+        assert_eq!(parse_args(std::iter::empty()).unwrap().days, 100);
+        assert!(parse_args(["--days"].iter().map(|s| s.to_string())).is_err());
+        assert!(parse_args(["--days", "x"].iter().map(|s| s.to_string())).is_err());
+    }
+
+    #[test]
+    fn radius_argument_test() {
+        let args = ["--radius", "2"];
+        let options = parse_args(args.iter().map(|s| s.to_string())).unwrap();
+        assert_eq!(options.radius, Some(2));
+        let floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        assert_eq!(floor.count_black_within(Coords(0, 0), 2), 7);
+
+        assert_eq!(parse_args(std::iter::empty()).unwrap().radius, None);
+        assert!(parse_args(["--radius"].iter().map(|s| s.to_string())).is_err());
+        assert!(parse_args(["--radius", "-1"].iter().map(|s| s.to_string())).is_err());
+    }
+
+    #[test]
+    fn rule_argument_test() {
+        let args = ["--rule", "12/2"];
+        let options = parse_args(args.iter().map(|s| s.to_string())).unwrap();
+        let (survive, birth) = options.rule.unwrap();
+        assert_eq!(survive, DEFAULT_SURVIVE);
+        assert_eq!(birth, DEFAULT_BIRTH);
+        let mut floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        assert_eq!(part2_with(&mut floor, 10, &survive, &birth), Ok(37));
+
+        let args = ["--rule", "/0"];
+        let (survive, birth) = parse_args(args.iter().map(|s| s.to_string()))
+            .unwrap()
+            .rule
+            .unwrap();
+        let mut floor = Floor::parse(TEST_INPUT.lines()).unwrap();
+        assert_eq!(part2_with(&mut floor, 1, &survive, &birth), Err(RuleError));
+
+        assert_eq!(parse_args(std::iter::empty()).unwrap().rule, None);
+        assert!(parse_args(["--rule"].iter().map(|s| s.to_string())).is_err());
+        assert!(parse_args(["--rule", "12"].iter().map(|s| s.to_string())).is_err());
+        assert!(parse_args(["--rule", "12/7"].iter().map(|s| s.to_string())).is_err());
+        assert!(parse_args(["--rule", "1/2/3"].iter().map(|s| s.to_string())).is_err());
+    }
 }
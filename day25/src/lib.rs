@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+pub use numtheory::modpow;
+
+pub const SUBJECT_NUMBER: u64 = 7;
+pub const ENCRYPTION_SIZE: u64 = 20201227;
+
+pub fn transform(subject: u64, loops: u64) -> u64 {
+    modpow(subject, loops, ENCRYPTION_SIZE)
+}
+
+pub fn loop_size(target: u64) -> Option<u64> {
+    let mut count = 0;
+    let mut value = 1;
+    while value != target {
+        value = (value * SUBJECT_NUMBER) % ENCRYPTION_SIZE;
+        count += 1;
+        if value == 1 {
+            return None;
+        }
+    }
+
+    Some(count)
+}
+
+// Baby-step giant-step discrete logarithm for a prime modulus: with m = ceil(sqrt(modulus)), any
+// exponent x can be written as x = i * m + j with 0 <= i, j < m, so store subject^j for each j and
+// then search for j among target * subject^(-i * m).
+pub struct DiscreteLog {
+    modulus: u64,
+    steps: u64,
+    baby_steps: HashMap<u64, u64>,
+    giant_step: u64,
+}
+
+impl DiscreteLog {
+    pub fn new(subject: u64, modulus: u64) -> Option<Self> {
+        if modulus < 2 {
+            return None;
+        }
+
+        let steps = (modulus as f64).sqrt().ceil() as u64;
+        let mut baby_steps = HashMap::with_capacity(steps as usize);
+        let mut value = 1;
+        for j in 0..steps {
+            baby_steps.entry(value).or_insert(j);
+            value = (value * subject) % modulus;
+        }
+
+        // subject^(modulus - 1) = 1 for prime modulus, so this is the inverse of subject^steps
+        let giant_step = modpow(subject, modulus - 1 - steps % (modulus - 1), modulus);
+        Some(Self {
+            modulus,
+            steps,
+            baby_steps,
+            giant_step,
+        })
+    }
+
+    pub fn log(&self, target: u64) -> Option<u64> {
+        if target >= self.modulus {
+            return None;
+        }
+
+        let mut value = target;
+        for i in 0..self.steps {
+            if let Some(j) = self.baby_steps.get(&value) {
+                return Some(i * self.steps + j);
+            }
+            value = (value * self.giant_step) % self.modulus;
+        }
+
+        None
+    }
+}
+
+pub fn loop_size_bsgs(target: u64) -> Option<u64> {
+    DiscreteLog::new(SUBJECT_NUMBER, ENCRYPTION_SIZE)?.log(target)
+}
+
+pub fn loop_sizes(public1: u64, public2: u64) -> Option<(u64, u64)> {
+    let discrete_log = DiscreteLog::new(SUBJECT_NUMBER, ENCRYPTION_SIZE)?;
+    Some((discrete_log.log(public1)?, discrete_log.log(public2)?))
+}
+
+// the key derived by each party from its own loop size and the other party's public key
+pub fn encryption_keys(public1: u64, public2: u64, (loop1, loop2): (u64, u64)) -> (u64, u64) {
+    (transform(public2, loop1), transform(public1, loop2))
+}
+
+pub fn verify(public1: u64, public2: u64) -> bool {
+    match loop_sizes(public1, public2) {
+        Some(loops) => {
+            let (key1, key2) = encryption_keys(public1, public2, loops);
+            key1 == key2
+        }
+        None => false,
+    }
+}
+
+pub fn find_key(first: u64, second: u64) -> Option<u64> {
+    Some(transform(second, loop_size_bsgs(first)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        encryption_keys, find_key, loop_size, loop_size_bsgs, loop_sizes, modpow, transform,
+        verify, DiscreteLog, ENCRYPTION_SIZE, SUBJECT_NUMBER,
+    };
+
+    #[test]
+    fn transform_test() {
+        assert_eq!(transform(7, 8), 5764801);
+        assert_eq!(transform(7, 11), 17807724);
+        assert_eq!(transform(17807724, 8), 14897079);
+        assert_eq!(transform(5764801, 11), 14897079);
+    }
+
+    #[test]
+    fn loop_size_test() {
+        assert_eq!(loop_size(5764801), Some(8));
+        assert_eq!(loop_size(17807724), Some(11));
+        assert_eq!(loop_size(1), Some(0));
+        assert_eq!(loop_size(0), None);
+    }
+
+    #[test]
+    fn loop_sizes_test() {
+        assert_eq!(loop_sizes(5764801, 17807724), Some((8, 11)));
+        assert_eq!(loop_sizes(5764801, 0), None);
+        let (loop1, loop2) = loop_sizes(1327981, 2822615).unwrap();
+        assert_eq!(
+            (Some(loop1), Some(loop2)),
+            (loop_size(1327981), loop_size(2822615))
+        );
+    }
+
+    #[test]
+    fn encryption_keys_test() {
+        assert_eq!(
+            encryption_keys(5764801, 17807724, (8, 11)),
+            (14897079, 14897079)
+        );
+        assert_eq!(encryption_keys(5764801, 17807724, (8, 10)).0, 14897079);
+        assert_ne!(encryption_keys(5764801, 17807724, (8, 10)).1, 14897079);
+    }
+
+    #[test]
+    fn loop_size_bsgs_test() {
+        assert_eq!(loop_size_bsgs(5764801), Some(8));
+        assert_eq!(loop_size_bsgs(17807724), Some(11));
+        assert_eq!(loop_size_bsgs(1), Some(0));
+        assert_eq!(loop_size_bsgs(7), Some(1));
+        for &target in &[2, 1327981, 2822615, ENCRYPTION_SIZE - 1] {
+            assert_eq!(loop_size_bsgs(target), loop_size(target));
+        }
+        assert_eq!(loop_size_bsgs(ENCRYPTION_SIZE), None);
+    }
+
+    #[test]
+    fn discrete_log_test() {
+        let discrete_log = DiscreteLog::new(SUBJECT_NUMBER, ENCRYPTION_SIZE).unwrap();
+        assert_eq!(discrete_log.log(5764801), Some(8));
+        assert_eq!(discrete_log.log(17807724), Some(11));
+        assert_eq!(discrete_log.log(0), None);
+
+        let discrete_log = DiscreteLog::new(2, 11).unwrap();
+        for x in 0..10 {
+            assert_eq!(discrete_log.log(modpow(2, x, 11)), Some(x));
+        }
+
+        assert!(DiscreteLog::new(SUBJECT_NUMBER, 1).is_none());
+        assert!(DiscreteLog::new(SUBJECT_NUMBER, 0).is_none());
+    }
+
+    #[test]
+    fn verify_test() {
+        assert!(verify(5764801, 17807724));
+        assert!(verify(17807724, 5764801));
+        assert!(!verify(5764801, 0));
+    }
+
+    #[test]
+    fn part1_test() {
+        let result = find_key(5764801, 17807724);
+        assert_eq!(result, Some(14897079));
+        assert_eq!(find_key(ENCRYPTION_SIZE, 17807724), None);
+    }
+}
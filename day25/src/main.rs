@@ -1,4 +1,13 @@
-use std::{error::Error, fmt, fs::read_to_string, path::PathBuf};
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+    time::Instant,
+};
+
+use day25::{encryption_keys, loop_size, loop_size_bsgs, loop_sizes};
 
 #[derive(Debug)]
 struct ParseError(&'static str);
@@ -11,54 +20,90 @@ impl fmt::Display for ParseError {
 
 impl Error for ParseError {}
 
-const SUBJECT_NUMBER: u64 = 7;
-const ENCRYPTION_SIZE: u64 = 20201227;
+#[derive(Debug)]
+enum KeyError {
+    Unreachable,
+    Mismatch,
+}
 
-fn loop_size(target: u64) -> u64 {
-    let mut count = 0;
-    let mut value = 1;
-    while value != target {
-        value = (value * SUBJECT_NUMBER) % ENCRYPTION_SIZE;
-        count += 1;
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreachable => write!(f, "Public key is not reachable"),
+            Self::Mismatch => write!(f, "Public keys do not derive the same encryption key"),
+        }
     }
+}
+
+impl Error for KeyError {}
 
-    count
+fn benchmark(target: u64) {
+    let start = Instant::now();
+    let result = loop_size(target).unwrap();
+    println!(
+        "Benchmark: naive loop size = {}, elapsed = {:?}",
+        result,
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let result = loop_size_bsgs(target).unwrap();
+    println!(
+        "Benchmark: baby-step giant-step loop size = {}, elapsed = {:?}",
+        result,
+        start.elapsed()
+    );
+}
+
+fn parse_keys(reader: impl BufRead) -> Result<(u64, u64), Box<dyn Error>> {
+    let mut lines = reader.lines();
+    let public1 = lines
+        .next()
+        .ok_or(ParseError("Missing first number"))??
+        .trim()
+        .parse()
+        .map_err(|_| ParseError("Invalid first number"))?;
+    let public2 = lines
+        .next()
+        .ok_or(ParseError("Missing second number"))??
+        .trim()
+        .parse()
+        .map_err(|_| ParseError("Invalid second number"))?;
+    Ok((public1, public2))
 }
 
-fn find_key(first: u64, mut second: u64) -> u64 {
-    let mut exponent = loop_size(first);
-    if exponent == 0 {
-        return 1;
+fn read_keys(path: Option<PathBuf>, stdin: impl BufRead) -> Result<(u64, u64), Box<dyn Error>> {
+    match path {
+        Some(path) => parse_keys(BufReader::new(File::open(path)?)),
+        None => parse_keys(stdin),
     }
-    let mut value = 1;
-    while exponent > 1 {
-        if exponent & 1 != 0 {
-            value = (second * value) % ENCRYPTION_SIZE;
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut benchmark_requested = false;
+    let mut path = Some(["data", "day25", "input.txt"].iter().collect::<PathBuf>());
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--benchmark" => benchmark_requested = true,
+            "-" => path = None,
+            _ => path = Some(PathBuf::from(arg)),
         }
-        second = (second * second) % ENCRYPTION_SIZE;
-        exponent >>= 1;
     }
 
-    (value * second) % ENCRYPTION_SIZE
-}
+    // a path of - reads the public keys from standard input
+    let (public1, public2) = read_keys(path, io::stdin().lock())?;
 
-fn run() -> Result<(), Box<dyn Error>> {
-    let (public1, public2) = {
-        let path = ["data", "day25", "input.txt"].iter().collect::<PathBuf>();
-        let input = read_to_string(path)?;
-        let mut values = input.lines().map(|s| s.parse().ok());
-        let public1 = values
-            .next()
-            .flatten()
-            .ok_or(ParseError("Missing first number"))?;
-        let public2 = values
-            .next()
-            .flatten()
-            .ok_or(ParseError("Missing second number"))?;
-        (public1, public2)
-    };
-
-    println!("Part 1: encrytion key = {}", find_key(public1, public2));
+    let (loop1, loop2) = loop_sizes(public1, public2).ok_or(KeyError::Unreachable)?;
+    println!("Part 1: loop sizes = {}, {}", loop1, loop2);
+    let (key1, key2) = encryption_keys(public1, public2, (loop1, loop2));
+    if key1 != key2 {
+        return Err(KeyError::Mismatch.into());
+    }
+    println!("Part 1: encrytion key = {}", key1);
+
+    if benchmark_requested {
+        benchmark(public1);
+    }
 
     Ok(())
 }
@@ -75,17 +120,38 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{find_key, loop_size};
+    use std::io::Cursor;
+
+    use day25::find_key;
+
+    use super::{parse_keys, read_keys};
+
+    #[test]
+    fn parse_keys_test() {
+        let keys = parse_keys(Cursor::new("5764801\n17807724\n")).unwrap();
+        assert_eq!(keys, (5764801, 17807724));
+
+        let error = parse_keys(Cursor::new("5764801\n")).unwrap_err();
+        assert_eq!(error.to_string(), "Parse error (Missing second number)");
+        let error = parse_keys(Cursor::new("5764801\nabc\n")).unwrap_err();
+        assert_eq!(error.to_string(), "Parse error (Invalid second number)");
+    }
 
     #[test]
-    fn loop_size_test() {
-        assert_eq!(loop_size(5764801), 8);
-        assert_eq!(loop_size(17807724), 11);
+    fn read_keys_test() {
+        let keys = read_keys(None, Cursor::new("17807724\n5764801\n")).unwrap();
+        assert_eq!(keys, (17807724, 5764801));
+
+        let error = read_keys(None, Cursor::new("")).unwrap_err();
+        assert_eq!(error.to_string(), "Parse error (Missing first number)");
+        let error = read_keys(None, Cursor::new("abc\n5764801\n")).unwrap_err();
+        assert_eq!(error.to_string(), "Parse error (Invalid first number)");
     }
 
     #[test]
-    fn part1_test() {
-        let result = find_key(5764801, 17807724);
-        assert_eq!(result, 14897079);
+    fn library_find_key_test() {
+        let (public1, public2) = parse_keys(Cursor::new("5764801\n17807724\n")).unwrap();
+        assert_eq!(find_key(public1, public2), Some(14897079));
+        assert_eq!(find_key(public2, public1), Some(14897079));
     }
 }
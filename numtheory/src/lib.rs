@@ -0,0 +1,122 @@
+use std::convert::TryFrom;
+
+pub fn modpow(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent & 1 != 0 {
+            result = (result * base) % modulus;
+        }
+        base = (base * base) % modulus;
+        exponent >>= 1;
+    }
+
+    result as u64
+}
+
+pub fn modular_inverse(a: i128, m: i128) -> Option<i128> {
+    // extended Euclidean algorithm to find inverse a_inv of a modulo m
+    // such that given y = x * a (mod m), x = y * a_inv (mod m)
+    let (mut t_prev, mut t_curr) = (0, 1);
+    let (mut r_prev, mut r_curr) = (m, a.rem_euclid(m));
+    while r_curr != 0 {
+        let quotient = r_prev / r_curr;
+        let t_next = t_prev - quotient * t_curr;
+        let r_next = r_prev - quotient * r_curr;
+        t_prev = std::mem::replace(&mut t_curr, t_next);
+        r_prev = std::mem::replace(&mut r_curr, r_next);
+    }
+
+    if r_prev > 1 {
+        None
+    } else {
+        Some(t_prev.rem_euclid(m))
+    }
+}
+
+pub fn crt(congruences: &[(i64, i64)]) -> Option<i64> {
+    // apply Chinese remainder theorem to equations x ≡ a_i (mod m_i), working in i128 since the
+    // product of the moduli may not fit in i64 even when the solution does; if it does not fit in
+    // i128 either, give up rather than overflow
+    let m_product = congruences
+        .iter()
+        .try_fold(1i128, |p, &(_, m)| p.checked_mul(i128::from(m)))?;
+    let mut sum = 0;
+    for &(a, m) in congruences {
+        let (a, m) = (i128::from(a), i128::from(m));
+        let n = m_product / m;
+        let y = modular_inverse(n, m)?;
+        // a * y * n (mod m * n) = (a * y mod m) * n, which is always less than m_product
+        // and so is sum, but their total may exceed i128::MAX, so reduce without adding
+        let term = a.rem_euclid(m) * y % m * n;
+        sum = if term >= m_product - sum {
+            sum - (m_product - term)
+        } else {
+            sum + term
+        };
+    }
+
+    i64::try_from(sum).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crt, modpow, modular_inverse};
+
+    #[test]
+    fn modpow_test() {
+        assert_eq!(modpow(2, 10, 1000), 24);
+        assert_eq!(modpow(3, 0, 7), 1);
+        assert_eq!(modpow(3, 0, 1), 0);
+        assert_eq!(modpow(0, 5, 13), 0);
+        assert_eq!(modpow(4, 13, 497), 445);
+        assert_eq!(modpow(7, 20201226, 20201227), 1);
+        assert_eq!(modpow(u64::MAX, 2, u64::MAX - 1), 1);
+    }
+
+    #[test]
+    fn modular_inverse_test() {
+        assert_eq!(modular_inverse(3, 11), Some(4));
+        assert_eq!(modular_inverse(10, 17), Some(12));
+        assert_eq!(modular_inverse(-3, 11), Some(7));
+        assert_eq!(modular_inverse(1, 1), Some(0));
+        assert_eq!(modular_inverse(6, 9), None);
+        assert_eq!(modular_inverse(2, (1 << 80) + 1), Some((1 << 79) + 1));
+    }
+
+    #[test]
+    fn crt_test() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some(23));
+        assert_eq!(
+            crt(&[(0, 7), (12, 13), (55, 59), (25, 31), (12, 19)]),
+            Some(1068781)
+        );
+        assert_eq!(crt(&[(-1, 4), (0, 3)]), Some(3));
+        assert_eq!(crt(&[]), Some(0));
+        assert_eq!(crt(&[(1, 4), (1, 6)]), None);
+
+        let moduli = [1_000_003, 1_000_033, 1_000_037, 1_000_039];
+        assert!(moduli
+            .iter()
+            .try_fold(1i64, |p, &m| p.checked_mul(m))
+            .is_none());
+        let x = 1_234_567_890_123_456_789;
+        let congruences = moduli.iter().map(|&m| (x % m, m)).collect::<Vec<_>>();
+        assert_eq!(crt(&congruences), Some(x));
+        assert_eq!(crt(&[(0, 1 << 40), (1, (1 << 40) + 1)]), None);
+
+        // the product of the moduli is between 2^126 and 2^127, so sums of terms can exceed
+        // i128::MAX
+        let m = 5_277_655_813_325;
+        let congruences = [m, m + 1, m + 2]
+            .iter()
+            .map(|&m| (x % m, m))
+            .collect::<Vec<_>>();
+        assert_eq!(crt(&congruences), Some(x));
+
+        // the product of the moduli does not fit in i128
+        let congruences = [(1, i64::MAX), (1, i64::MAX - 1), (1, i64::MAX - 2)];
+        assert_eq!(crt(&congruences), None);
+    }
+}